@@ -0,0 +1,4 @@
+ALTER TABLE
+    NUMBERS
+ADD
+    COLUMN overflow TEXT[];
\ No newline at end of file
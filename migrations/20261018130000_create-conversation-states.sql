@@ -0,0 +1,7 @@
+CREATE TABLE conversation_states (
+    number VARCHAR(255) PRIMARY KEY REFERENCES numbers(number),
+    state JSONB NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
\ No newline at end of file
@@ -0,0 +1,6 @@
+CREATE TYPE times_format AS ENUM ('lines', 'grouped');
+
+ALTER TABLE
+    NUMBERS
+ADD
+    COLUMN times_format times_format NOT NULL DEFAULT 'lines';
\ No newline at end of file
@@ -0,0 +1,4 @@
+ALTER TABLE
+    NUMBERS
+ADD
+    COLUMN countdown_horizon INTEGER;
\ No newline at end of file
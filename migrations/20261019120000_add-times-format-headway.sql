@@ -0,0 +1 @@
+ALTER TYPE times_format ADD VALUE 'headway';
\ No newline at end of file
@@ -0,0 +1,12 @@
+CREATE TABLE aliases (
+    id UUID PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    query VARCHAR(255),
+    latitude DOUBLE PRECISION,
+    longitude DOUBLE PRECISION,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL,
+    CHECK (query IS NOT NULL OR (latitude IS NOT NULL AND longitude IS NOT NULL))
+);
+
+CREATE UNIQUE INDEX aliases_name_index ON aliases (LOWER(name));
\ No newline at end of file
@@ -0,0 +1,4 @@
+ALTER TABLE messages
+ADD COLUMN num_media INTEGER,
+ADD COLUMN from_city VARCHAR(255),
+ADD COLUMN sms_status VARCHAR(32);
\ No newline at end of file
@@ -0,0 +1,2 @@
+ALTER TABLE messages
+ADD COLUMN send_error TEXT;
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE messages
+ADD COLUMN delivery_status VARCHAR(32),
+ADD COLUMN delivery_error_code INTEGER;
\ No newline at end of file
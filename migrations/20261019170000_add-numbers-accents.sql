@@ -0,0 +1,4 @@
+ALTER TABLE
+    NUMBERS
+ADD
+    COLUMN accents BOOLEAN NOT NULL DEFAULT false;
\ No newline at end of file
@@ -0,0 +1,13 @@
+UPDATE messages
+SET message_sid = NULL
+WHERE initial_message_id IS NULL
+    AND message_sid <> 'repl'
+    AND id NOT IN (
+        SELECT DISTINCT ON (message_sid) id
+        FROM messages
+        WHERE initial_message_id IS NULL
+        ORDER BY message_sid, created_at
+    );
+
+CREATE UNIQUE INDEX messages_incoming_message_sid_index ON messages (message_sid)
+WHERE initial_message_id IS NULL AND message_sid <> 'repl';
\ No newline at end of file
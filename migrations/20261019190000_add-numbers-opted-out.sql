@@ -0,0 +1,2 @@
+ALTER TABLE numbers
+ADD COLUMN opted_out BOOLEAN NOT NULL DEFAULT false;
\ No newline at end of file
@@ -0,0 +1,6 @@
+ALTER TABLE numbers
+DROP COLUMN overflow;
+
+ALTER TABLE conversation_states
+ALTER COLUMN state DROP NOT NULL,
+ADD COLUMN overflow TEXT[];
@@ -0,0 +1,2 @@
+ALTER TABLE messages
+ADD COLUMN sent_via_api BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,6 @@
+ALTER TABLE conversation_states
+ADD COLUMN overflow_expires_at TIMESTAMP;
+
+UPDATE conversation_states
+SET overflow_expires_at = expires_at
+WHERE overflow IS NOT NULL;
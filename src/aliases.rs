@@ -0,0 +1,16 @@
+use sqlx::PgPool;
+
+use crate::models::Alias;
+
+// Admin-managed names for places the locations search doesn’t know, like “the forks”
+pub async fn find_alias(db: &PgPool, name: &str) -> Result<Option<Alias>, sqlx::Error> {
+    sqlx::query_as::<_, Alias>(
+        r#"
+        SELECT * FROM aliases
+        WHERE LOWER(name) = LOWER($1)
+        "#,
+    )
+    .bind(name.trim())
+    .fetch_optional(db)
+    .await
+}
@@ -1,8 +1,16 @@
+mod choice;
+mod more;
+mod near;
+mod opt_out;
 mod parse;
 mod settings;
 mod stops;
 mod times;
 
+pub use choice::*;
+pub use more::*;
+pub use near::*;
+pub use opt_out::*;
 pub use parse::*;
 pub use settings::*;
 pub use stops::*;
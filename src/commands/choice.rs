@@ -0,0 +1,101 @@
+use sqlx::{types::Uuid, PgPool};
+
+use crate::{
+    commands::{
+        handle_times_request, near::handle_near_location, stops::handle_stops_near_location,
+        ChoiceCommand, TimesCommand,
+    },
+    config::Config,
+    conversation::{get_conversation_state, ConversationState},
+    models::Number,
+};
+
+pub async fn handle_choice_request(
+    command: ChoiceCommand,
+    config: &Config,
+    winnipeg_transit_api_address: String,
+    maybe_incoming_message_id: Option<Uuid>,
+    db: &PgPool,
+    number: &Option<Number>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let state = if let Some(existing_number) = number {
+        get_conversation_state(db, &existing_number.number).await?
+    } else {
+        return Ok("Cannot choose from a list with this interface".to_string());
+    };
+
+    match state {
+        Some(ConversationState::Stops { stop_numbers }) => {
+            let chosen_stop_number = command
+                .choice
+                .checked_sub(1)
+                .and_then(|index| stop_numbers.get(index));
+
+            if let Some(stop_number) = chosen_stop_number {
+                handle_times_request(
+                    TimesCommand {
+                        stop_numbers: vec![stop_number.to_string()],
+                        routes: Vec::new(),
+                        destination: None,
+                        direction: None,
+                    },
+                    config,
+                    winnipeg_transit_api_address,
+                    maybe_incoming_message_id,
+                    db,
+                    number,
+                )
+                .await
+            } else {
+                Ok(format!(
+                    "No stop {} in the last stops list, choose 1 to {}",
+                    command.choice,
+                    stop_numbers.len()
+                ))
+            }
+        }
+        Some(ConversationState::Locations {
+            routes,
+            locations,
+            near,
+        }) => {
+            let chosen_location = command
+                .choice
+                .checked_sub(1)
+                .and_then(|index| locations.get(index));
+
+            if let Some(location) = chosen_location {
+                if near {
+                    return handle_near_location(
+                        location.clone(),
+                        &routes,
+                        config,
+                        winnipeg_transit_api_address,
+                        maybe_incoming_message_id,
+                        db,
+                        number,
+                    )
+                    .await;
+                }
+
+                handle_stops_near_location(
+                    location.clone(),
+                    &routes,
+                    config,
+                    winnipeg_transit_api_address,
+                    maybe_incoming_message_id,
+                    db,
+                    number,
+                )
+                .await
+            } else {
+                Ok(format!(
+                    "No place {} in the last list, choose 1 to {}",
+                    command.choice,
+                    locations.len()
+                ))
+            }
+        }
+        None => Ok("No recent list to choose from, try stops [location]".to_string()),
+    }
+}
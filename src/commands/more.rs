@@ -0,0 +1,291 @@
+use crate::{
+    config::Config,
+    conversation::{clear_overflow, get_overflow, set_overflow},
+    encoding::{prepare_outbound, segment_count},
+    models::Number,
+};
+use sqlx::PgPool;
+
+pub const MORE_HINT: &str = "(more)";
+
+// Interfaces without a number can’t page, so they’re told what was left out
+pub const TRUNCATED_HINT: &str = "(more via SMS)";
+
+pub async fn handle_more_request(
+    config: &Config,
+    db: &PgPool,
+    number: &Option<Number>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let response_text = if let Some(existing_number) = number {
+        match get_overflow(db, &existing_number.number).await? {
+            Some(overflow) if !overflow.is_empty() => {
+                paginate(String::new(), overflow, config, db, number).await?
+            }
+            _ => "Nothing more to send".to_string(),
+        }
+    } else {
+        "Cannot get more with this interface".to_string()
+    };
+
+    Ok(response_text)
+}
+
+pub async fn clear_pending_pages(db: &PgPool, number: &Option<Number>) -> Result<(), sqlx::Error> {
+    if let Some(number) = number {
+        clear_overflow(db, &number.number).await?;
+    }
+
+    Ok(())
+}
+
+// Appends as many entries as fit the segment budget and stores the rest for a later `more`.
+pub async fn paginate(
+    response_text: String,
+    entries: Vec<String>,
+    config: &Config,
+    db: &PgPool,
+    number: &Option<Number>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    // Counting what’s actually sent, transliteration can let a page fit more
+    let mut response_text = prepare_outbound(&response_text, number.as_ref());
+    let entries: Vec<String> = entries
+        .iter()
+        .map(|entry| prepare_outbound(entry, number.as_ref()))
+        .collect();
+
+    let segment_budget = config.response_segment_budget;
+    let mut page_length = count_fitting_entries(&response_text, &entries, "", segment_budget);
+
+    let hint = if number.is_some() {
+        MORE_HINT
+    } else {
+        TRUNCATED_HINT
+    };
+
+    if page_length < entries.len() {
+        page_length = count_fitting_entries(&response_text, &entries, hint, segment_budget);
+    }
+
+    // Always send at least one entry so an oversized one can’t stall paging
+    page_length = page_length.max(1).min(entries.len());
+
+    for entry in &entries[..page_length] {
+        response_text.push_str(&format!("{}\n", entry));
+    }
+
+    if let Some(number) = number {
+        let overflow = &entries[page_length..];
+
+        if overflow.is_empty() {
+            clear_overflow(db, &number.number).await?;
+        } else {
+            set_overflow(db, &number.number, overflow.to_vec()).await?;
+            response_text.push_str(hint);
+        }
+    } else if page_length < entries.len() {
+        response_text.push_str(hint);
+    }
+
+    Ok(response_text)
+}
+
+pub fn fits_on_one_page(
+    response_text: &str,
+    entries: &[String],
+    config: &Config,
+    number: &Option<Number>,
+) -> bool {
+    let entries: Vec<String> = entries
+        .iter()
+        .map(|entry| prepare_outbound(entry, number.as_ref()))
+        .collect();
+
+    count_fitting_entries(
+        &prepare_outbound(response_text, number.as_ref()),
+        &entries,
+        "",
+        config.response_segment_budget,
+    ) == entries.len()
+}
+
+// Renders each group as one entry where it fits what’s left of a page and otherwise continues it onto
+// following pages with the label repeated, so labels only repeat across pages. A group without
+// items is rendered as its label alone.
+pub fn split_groups(
+    response_text: &str,
+    groups: Vec<(String, Vec<String>)>,
+    config: &Config,
+    number: &Option<Number>,
+) -> Vec<String> {
+    let hint = if number.is_some() {
+        MORE_HINT
+    } else {
+        TRUNCATED_HINT
+    };
+
+    split_groups_within(
+        response_text,
+        groups,
+        hint,
+        config.response_segment_budget,
+        number.as_ref(),
+    )
+}
+
+fn split_groups_within(
+    response_text: &str,
+    groups: Vec<(String, Vec<String>)>,
+    hint: &str,
+    segment_budget: usize,
+    number: Option<&Number>,
+) -> Vec<String> {
+    let fits = |page: &str, entry: &str| {
+        segment_count(&prepare_outbound(
+            &format!("{}{}\n{}", page, entry, hint),
+            number,
+        )) <= segment_budget
+    };
+
+    let mut entries = Vec::new();
+    let mut page = response_text.to_string();
+
+    for (label, items) in groups {
+        let entry = render_group(&label, &items);
+
+        if fits(&page, &entry) {
+            page.push_str(&format!("{}\n", entry));
+            entries.push(entry);
+            continue;
+        }
+
+        if items.is_empty() || fits("", &entry) {
+            page = format!("{}\n", entry);
+            entries.push(entry);
+            continue;
+        }
+
+        let mut remaining = &items[..];
+
+        while !remaining.is_empty() {
+            let fitting_count = (1..=remaining.len())
+                .take_while(|count| fits(&page, &render_group(&label, &remaining[..*count])))
+                .count();
+
+            if fitting_count == 0 && !page.is_empty() {
+                page = String::new();
+                continue;
+            }
+
+            // Always take at least one item so an oversized one can’t stall splitting
+            let (taken, rest) = remaining.split_at(fitting_count.max(1));
+            let entry = render_group(&label, taken);
+
+            page.push_str(&format!("{}\n", entry));
+            entries.push(entry);
+
+            if !rest.is_empty() {
+                page = String::new();
+            }
+
+            remaining = rest;
+        }
+    }
+
+    entries
+}
+
+fn render_group(label: &str, items: &[String]) -> String {
+    if items.is_empty() {
+        label.to_string()
+    } else {
+        format!("{}: {}", label, items.join(" "))
+    }
+}
+
+// Counts the entries that fit with the hint appended, a single unusual character can switch the
+// whole page to UCS-2 and shrink what fits
+fn count_fitting_entries(
+    response_text: &str,
+    entries: &[String],
+    hint: &str,
+    segment_budget: usize,
+) -> usize {
+    let mut page = response_text.to_string();
+    let mut count = 0;
+
+    for entry in entries {
+        page.push_str(entry);
+        page.push('\n');
+
+        if segment_count(&format!("{}{}", page, hint)) > segment_budget {
+            break;
+        }
+
+        count += 1;
+    }
+
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_fitting_entries() {
+        let entries = vec!["a".repeat(70), "b".repeat(70), "c".repeat(70)];
+
+        assert_eq!(count_fitting_entries("", &entries, "", 1), 2);
+        assert_eq!(count_fitting_entries(&"h".repeat(20), &entries, "", 1), 1);
+        assert_eq!(count_fitting_entries(&"h".repeat(18), &entries, "", 1), 2);
+        assert_eq!(
+            count_fitting_entries(&"h".repeat(18), &entries, MORE_HINT, 1),
+            1
+        );
+        assert_eq!(count_fitting_entries("", &entries, "", 2), 3);
+    }
+
+    #[test]
+    fn test_split_groups() {
+        let times: Vec<String> = (10..40).map(|minute| format!("12:{}p", minute)).collect();
+
+        let entries = split_groups_within(
+            "header\n",
+            vec![
+                ("16 St Vital".to_string(), times[..2].to_vec()),
+                ("BLUE Downtown".to_string(), times.clone()),
+                ("every 5 min".to_string(), Vec::new()),
+            ],
+            MORE_HINT,
+            1,
+            None,
+        );
+
+        assert_eq!(entries[0], "16 St Vital: 12:10p 12:11p");
+        assert!(entries[1].starts_with("BLUE Downtown: 12:10p"));
+        assert!(entries[2].starts_with("BLUE Downtown: "));
+        assert_eq!(entries.last().unwrap(), "every 5 min");
+        assert_eq!(entries.len(), 4);
+        assert_eq!(
+            segment_count(&format!(
+                "header\n{}\n{}\n{}",
+                entries[0], entries[1], MORE_HINT
+            )),
+            1
+        );
+        assert!(
+            segment_count(&format!(
+                "header\n{}\n{} 12:10p\n{}",
+                entries[0], entries[1], MORE_HINT
+            )) > 1
+        );
+    }
+
+    #[test]
+    fn test_count_fitting_entries_in_ucs2() {
+        let entries = vec!["a".repeat(30), "b".repeat(30), "c".repeat(30)];
+
+        assert_eq!(count_fitting_entries("", &entries, "", 1), 3);
+        assert_eq!(count_fitting_entries("’\n", &entries, "", 1), 2);
+    }
+}
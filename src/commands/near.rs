@@ -0,0 +1,140 @@
+use futures::future::join_all;
+use sqlx::{types::Uuid, PgPool};
+
+use crate::{
+    commands::{
+        stops::{
+            direction_abbreviation, find_nearby_stops, locate, offer_location_choices, Located,
+            Stop, UNREADABLE_LINK_MESSAGE,
+        },
+        times::{fetch_stop_departures, render_departures},
+        NearCommand,
+    },
+    config::Config,
+    conversation::LocationChoice,
+    models::Number,
+};
+
+pub async fn handle_near_request(
+    command: NearCommand,
+    config: &Config,
+    winnipeg_transit_api_address: String,
+    maybe_incoming_message_id: Option<Uuid>,
+    db: &PgPool,
+    number: &Option<Number>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let location = match locate(
+        &command.location,
+        config,
+        winnipeg_transit_api_address.clone(),
+        maybe_incoming_message_id,
+        db,
+    )
+    .await
+    {
+        Located::Found(location) => location,
+        Located::Ambiguous(locations) => {
+            return offer_location_choices(
+                &command.location,
+                &command.routes,
+                true,
+                locations,
+                config,
+                db,
+                number,
+            )
+            .await
+        }
+        Located::NotFound => return Ok(format!("No locations found for {}", command.location)),
+        Located::UnreadableLink => return Ok(UNREADABLE_LINK_MESSAGE.to_string()),
+    };
+
+    handle_near_location(
+        location,
+        &command.routes,
+        config,
+        winnipeg_transit_api_address,
+        maybe_incoming_message_id,
+        db,
+        number,
+    )
+    .await
+}
+
+pub(super) async fn handle_near_location(
+    location: LocationChoice,
+    routes: &[String],
+    config: &Config,
+    winnipeg_transit_api_address: String,
+    maybe_incoming_message_id: Option<Uuid>,
+    db: &PgPool,
+    number: &Option<Number>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (_, stops) = find_nearby_stops(
+        &location.latitude,
+        &location.longitude,
+        routes,
+        config,
+        winnipeg_transit_api_address.clone(),
+        maybe_incoming_message_id,
+        db,
+    )
+    .await?;
+
+    // Stops come closest in each direction first, so the first stop per direction is the closest one
+    let mut chosen_stops: Vec<&Stop> = Vec::new();
+
+    for stop in &stops {
+        if !chosen_stops
+            .iter()
+            .any(|chosen| chosen.direction == stop.direction)
+        {
+            chosen_stops.push(stop);
+        }
+    }
+
+    let departures: Vec<_> = join_all(chosen_stops.iter().map(|stop| {
+        let line_prefix = match &stop.direction {
+            Some(direction) => format!("{} {} ", stop.number, direction_abbreviation(direction)),
+            None => format!("{} ", stop.number),
+        };
+
+        let stop_number = stop.number.to_string();
+        let winnipeg_transit_api_address = winnipeg_transit_api_address.clone();
+
+        async move {
+            fetch_stop_departures(
+                &stop_number,
+                routes,
+                &line_prefix,
+                config,
+                winnipeg_transit_api_address,
+                maybe_incoming_message_id,
+                db,
+            )
+            .await
+            .unwrap_or_default()
+        }
+    }))
+    .await
+    .into_iter()
+    .flatten()
+    .collect();
+
+    if departures.is_empty() {
+        return Ok(format!(
+            "No departures found for {} near {}",
+            routes.join(" "),
+            location.name
+        ));
+    }
+
+    render_departures(
+        format!("{} near {}\n", routes.join(" "), location.name),
+        departures,
+        config,
+        db,
+        number,
+    )
+    .await
+}
@@ -0,0 +1,64 @@
+use chrono::Utc;
+use sqlx::PgPool;
+
+#[derive(Debug, PartialEq)]
+pub enum OptOutKeyword {
+    Stop,
+    Start,
+}
+
+// Carriers require the standard keywords to work on their own, before any other command
+pub fn parse_opt_out_keyword(input: &str) -> Option<OptOutKeyword> {
+    match input.trim().to_lowercase().as_str() {
+        "stop" | "stopall" | "unsubscribe" | "cancel" | "end" | "quit" => Some(OptOutKeyword::Stop),
+        "start" | "unstop" | "yes" => Some(OptOutKeyword::Start),
+        _ => None,
+    }
+}
+
+pub async fn handle_opt_out_keyword(
+    keyword: OptOutKeyword,
+    db: &PgPool,
+    number: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let opted_out = keyword == OptOutKeyword::Stop;
+
+    sqlx::query(
+        "INSERT INTO numbers (number, opted_out, created_at, updated_at)
+        VALUES ($1, $2, $3, $3)
+        ON CONFLICT (number) DO UPDATE
+        SET opted_out = $2, updated_at = $3",
+    )
+    .bind(number)
+    .bind(opted_out)
+    .bind(Utc::now().naive_utc())
+    .execute(db)
+    .await?;
+
+    let response_text = if opted_out {
+        "you’re unsubscribed from textabus and won’t get any more texts, reply start to resubscribe"
+    } else {
+        "you’re resubscribed to textabus, reply help for commands"
+    };
+
+    Ok(response_text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_opt_out_keyword() {
+        assert_eq!(parse_opt_out_keyword("STOP"), Some(OptOutKeyword::Stop));
+        assert_eq!(
+            parse_opt_out_keyword(" Unsubscribe "),
+            Some(OptOutKeyword::Stop)
+        );
+        assert_eq!(parse_opt_out_keyword("cancel"), Some(OptOutKeyword::Stop));
+        assert_eq!(parse_opt_out_keyword("Start"), Some(OptOutKeyword::Start));
+        assert_eq!(parse_opt_out_keyword("UNSTOP"), Some(OptOutKeyword::Start));
+        assert_eq!(parse_opt_out_keyword("stop 10619"), None);
+        assert_eq!(parse_opt_out_keyword("stops osborne"), None);
+    }
+}
@@ -1,4 +1,7 @@
 use regex::Regex;
+use url::{form_urlencoded, Url};
+
+use crate::{commands::DEFAULT_COUNTDOWN_HORIZON, models::TimesFormat};
 
 pub fn parse_command(input: &str) -> Command {
     let cleaned_input = clean_input(input);
@@ -11,10 +14,38 @@ pub fn parse_command(input: &str) -> Command {
         return Command::Stops(command);
     }
 
+    if let Ok(command) = parse_routes_near_location(&cleaned_input) {
+        return Command::Near(command);
+    }
+
+    if let Ok(command) = parse_service(&cleaned_input) {
+        return Command::Service(command);
+    }
+
+    if let Ok(command) = parse_choice(&cleaned_input) {
+        return Command::Choice(command);
+    }
+
     if let Ok(command) = parse_settings_clock(&cleaned_input) {
         return Command::SettingsClock(command);
     }
 
+    if let Ok(command) = parse_settings_format(&cleaned_input) {
+        return Command::SettingsFormat(command);
+    }
+
+    if let Ok(command) = parse_settings_countdown(&cleaned_input) {
+        return Command::SettingsCountdown(command);
+    }
+
+    if let Ok(command) = parse_settings_accents(&cleaned_input) {
+        return Command::SettingsAccents(command);
+    }
+
+    if let Ok(command) = parse_more(&cleaned_input) {
+        return Command::More(command);
+    }
+
     if let Ok(command) = parse_help(&cleaned_input) {
         return Command::Help(command);
     }
@@ -35,19 +66,46 @@ fn clean_input(input: &str) -> String {
 }
 
 fn parse_stop_and_routes(input: &str) -> Result<TimesCommand, &'static str> {
-    let re = Regex::new(r"^(times )?(\d{5})(?:\s+(.*))?$").unwrap();
+    let re = Regex::new(r"^(times )?(\d{5}(?:\s+\d{5})*)(?:\s+(.*))?$").unwrap();
 
     if let Some(captures) = re.captures(input) {
-        let stop_number = captures.get(2).map_or("", |m| m.as_str()).to_string();
-        let routes: Vec<String> = captures
-            .get(3)
+        let stop_numbers: Vec<String> = captures
+            .get(2)
             .map_or("", |m| m.as_str())
             .split_whitespace()
             .map(|s| s.to_string())
             .collect();
+        let mut filters: Vec<&str> = captures
+            .get(3)
+            .map_or("", |m| m.as_str())
+            .split_whitespace()
+            .collect();
+
+        let mut destination = None;
+
+        if let Some(to_position) = filters.iter().position(|f| f.eq_ignore_ascii_case("to")) {
+            if to_position + 1 < filters.len() {
+                destination = Some(filters[to_position + 1..].join(" "));
+                filters.truncate(to_position);
+            }
+        }
+
+        let mut direction = None;
+        let mut routes: Vec<String> = Vec::new();
+
+        for filter in filters {
+            if let Some(filter_direction) = Direction::from_word(filter) {
+                direction = Some(filter_direction);
+            } else {
+                routes.push(filter.to_string());
+            }
+        }
+
         Ok(TimesCommand {
-            stop_number,
+            stop_numbers,
             routes,
+            destination,
+            direction,
         })
     } else {
         Err("Input string doesn't match the expected pattern")
@@ -56,15 +114,168 @@ fn parse_stop_and_routes(input: &str) -> Result<TimesCommand, &'static str> {
 
 fn parse_stops_and_location(input: &str) -> Result<StopsCommand, &'static str> {
     let re = Regex::new(r"^stops\s+(.*)$").unwrap();
+    let route_word = Regex::new(r"(?i)^(\d{1,3}|blue|[a-z]{1,2}\d{1,2})$").unwrap();
 
     if let Some(captures) = re.captures(input) {
-        let location = captures.get(1).map_or("", |m| m.as_str()).to_string();
-        Ok(StopsCommand { location })
+        let mut words: Vec<&str> = captures
+            .get(1)
+            .map_or("", |m| m.as_str())
+            .split(' ')
+            .collect();
+
+        // Routes follow an explicit keyword, since place names can end in a number like a route
+        let mut routes: Vec<&str> = Vec::new();
+
+        let keyword_position = words.iter().rposition(|word| {
+            ["route", "routes", "on"]
+                .iter()
+                .any(|keyword| word.eq_ignore_ascii_case(keyword))
+        });
+
+        if let Some(position) = keyword_position.filter(|&position| {
+            position > 0
+                && position < words.len() - 1
+                && words[position + 1..]
+                    .iter()
+                    .all(|word| route_word.is_match(word))
+        }) {
+            routes = words.split_off(position + 1);
+            words.pop();
+        } else {
+            // Coordinates can’t be mistaken for a place, so routes can follow them directly
+            let route_count = words
+                .iter()
+                .rev()
+                .take_while(|word| route_word.is_match(word))
+                .count();
+
+            if route_count > 0
+                && route_count < words.len()
+                && parse_coordinates(&words[..words.len() - route_count].join(" ")).is_some()
+            {
+                routes = words.split_off(words.len() - route_count);
+            }
+        }
+
+        let routes: Vec<String> = routes.into_iter().map(|route| route.to_string()).collect();
+
+        let location = words.join(" ");
+        let coordinates = parse_coordinates(&location);
+
+        Ok(StopsCommand {
+            location,
+            routes,
+            coordinates,
+        })
     } else {
         Err("Input string does not match a stops request")
     }
 }
 
+// Raw “lat,lon”, geo: URIs and Google or Apple Maps links carry coordinates directly
+fn parse_coordinates(location: &str) -> Option<(f64, f64)> {
+    // The pair must end the text or be followed by URI punctuation, so “1,2 portage” is a place
+    let pair =
+        Regex::new(r"^(-?\d{1,3}(?:\.\d+)?),\s*(-?\d{1,3}(?:\.\d+)?)(?:$|[,;?&/(])").unwrap();
+
+    let candidate = if let Some(geo) = location.strip_prefix("geo:") {
+        // A q= search names the point when the path is the 0,0 placeholder
+        let (path, query) = geo.split_once('?').unwrap_or((geo, ""));
+
+        form_urlencoded::parse(query.as_bytes())
+            .find(|(key, value)| key == "q" && pair.is_match(value))
+            .map(|(_, value)| value.to_string())
+            .or_else(|| Some(path.to_string()))
+    } else if let Ok(url) = Url::parse(location) {
+        let host = url.host_str().unwrap_or_default();
+
+        if host.contains("google.") || host == "maps.apple.com" {
+            url.query_pairs()
+                .find(|(key, value)| {
+                    ["q", "query", "ll", "sll", "daddr", "coordinate"].contains(&key.as_ref())
+                        && pair.is_match(value)
+                })
+                .map(|(_, value)| value.to_string())
+                .or_else(|| {
+                    url.path()
+                        .split_once('@')
+                        .map(|(_, after_at)| after_at.to_string())
+                })
+        } else {
+            None
+        }
+    } else {
+        Some(location.to_string())
+    };
+
+    let candidate = candidate?;
+    let captures = pair.captures(&candidate)?;
+    let latitude: f64 = captures.get(1)?.as_str().parse().ok()?;
+    let longitude: f64 = captures.get(2)?.as_str().parse().ok()?;
+
+    if latitude.abs() <= 90.0 && longitude.abs() <= 180.0 {
+        Some((latitude, longitude))
+    } else {
+        None
+    }
+}
+
+fn parse_routes_near_location(input: &str) -> Result<NearCommand, &'static str> {
+    let re = Regex::new(r"(?i)^((?:[a-z0-9]{1,5} )+)near (.+)$").unwrap();
+
+    if let Some(captures) = re.captures(input) {
+        let routes = captures
+            .get(1)
+            .unwrap()
+            .as_str()
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect();
+        let location = captures.get(2).unwrap().as_str().to_string();
+
+        Ok(NearCommand { routes, location })
+    } else {
+        Err("Input string does not match a routes near location request")
+    }
+}
+
+fn parse_service(input: &str) -> Result<ServiceCommand, &'static str> {
+    let re = Regex::new(r"^(last|first) (\d{5})(?:\s+(.*))?$").unwrap();
+
+    if let Some(captures) = re.captures(input) {
+        let boundary = match captures.get(1).unwrap().as_str() {
+            "first" => ServiceBoundary::First,
+            _ => ServiceBoundary::Last,
+        };
+        let stop_number = captures.get(2).unwrap().as_str().to_string();
+        let routes = captures
+            .get(3)
+            .map_or("", |m| m.as_str())
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect();
+
+        Ok(ServiceCommand {
+            boundary,
+            stop_number,
+            routes,
+        })
+    } else {
+        Err("Input string does not match a last or first bus request")
+    }
+}
+
+fn parse_choice(input: &str) -> Result<ChoiceCommand, &'static str> {
+    let re = Regex::new(r"^(\d{1,2})$").unwrap();
+
+    if let Some(captures) = re.captures(input) {
+        let choice = captures.get(1).unwrap().as_str().parse().unwrap();
+        Ok(ChoiceCommand { choice })
+    } else {
+        Err("Input string does not match a choice from a list")
+    }
+}
+
 fn parse_settings_clock(input: &str) -> Result<SettingsClockCommand, &'static str> {
     let re = Regex::new(r"(?i)^settings clock$").unwrap();
 
@@ -75,6 +286,57 @@ fn parse_settings_clock(input: &str) -> Result<SettingsClockCommand, &'static st
     }
 }
 
+fn parse_settings_format(input: &str) -> Result<SettingsFormatCommand, &'static str> {
+    let re = Regex::new(r"(?i)^settings format (lines|grouped|headway)$").unwrap();
+
+    if let Some(captures) = re.captures(input) {
+        let format = match captures.get(1).unwrap().as_str().to_lowercase().as_str() {
+            "grouped" => TimesFormat::Grouped,
+            "headway" => TimesFormat::Headway,
+            _ => TimesFormat::Lines,
+        };
+        Ok(SettingsFormatCommand { format })
+    } else {
+        Err("Input string does not match a settings format request")
+    }
+}
+
+fn parse_settings_countdown(input: &str) -> Result<SettingsCountdownCommand, &'static str> {
+    let re = Regex::new(r"(?i)^settings countdown(?: (\d{1,2}|off))?$").unwrap();
+
+    if let Some(captures) = re.captures(input) {
+        let horizon = match captures.get(1).map(|m| m.as_str().to_lowercase()) {
+            Some(value) if value == "off" => None,
+            Some(minutes) => Some(minutes.parse().unwrap()),
+            None => Some(DEFAULT_COUNTDOWN_HORIZON),
+        };
+        Ok(SettingsCountdownCommand { horizon })
+    } else {
+        Err("Input string does not match a settings countdown request")
+    }
+}
+
+fn parse_settings_accents(input: &str) -> Result<SettingsAccentsCommand, &'static str> {
+    let re = Regex::new(r"(?i)^settings accents (on|off)$").unwrap();
+
+    if let Some(captures) = re.captures(input) {
+        let accents = captures.get(1).unwrap().as_str().to_lowercase() == "on";
+        Ok(SettingsAccentsCommand { accents })
+    } else {
+        Err("Input string does not match a settings accents request")
+    }
+}
+
+fn parse_more(input: &str) -> Result<MoreCommand, &'static str> {
+    let re = Regex::new(r"^more$").unwrap();
+
+    if let Some(_captures) = re.captures(input) {
+        Ok(MoreCommand {})
+    } else {
+        Err("Input string does not match a more request")
+    }
+}
+
 fn parse_help(input: &str) -> Result<HelpCommand, &'static str> {
     let re = Regex::new(r"^help").unwrap();
 
@@ -88,22 +350,97 @@ fn parse_help(input: &str) -> Result<HelpCommand, &'static str> {
 pub enum Command {
     Times(TimesCommand),
     Stops(StopsCommand),
+    Near(NearCommand),
+    Service(ServiceCommand),
+    Choice(ChoiceCommand),
     SettingsClock(SettingsClockCommand),
+    SettingsFormat(SettingsFormatCommand),
+    SettingsCountdown(SettingsCountdownCommand),
+    SettingsAccents(SettingsAccentsCommand),
+    More(MoreCommand),
     Help(HelpCommand),
     Unknown(UnknownCommand),
 }
 
 pub struct TimesCommand {
-    pub stop_number: String,
+    pub stop_numbers: Vec<String>,
     pub routes: Vec<String>,
+    pub destination: Option<String>,
+    pub direction: Option<Direction>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Direction {
+    fn from_word(word: &str) -> Option<Direction> {
+        match word.to_lowercase().as_str() {
+            "north" | "nb" | "northbound" => Some(Direction::North),
+            "south" | "sb" | "southbound" => Some(Direction::South),
+            "east" | "eb" | "eastbound" => Some(Direction::East),
+            "west" | "wb" | "westbound" => Some(Direction::West),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Direction::North => "north",
+            Direction::South => "south",
+            Direction::East => "east",
+            Direction::West => "west",
+        }
+    }
 }
 
 pub struct StopsCommand {
     pub location: String,
+    pub routes: Vec<String>,
+    pub coordinates: Option<(f64, f64)>,
+}
+
+pub struct NearCommand {
+    pub routes: Vec<String>,
+    pub location: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ServiceBoundary {
+    First,
+    Last,
+}
+
+pub struct ServiceCommand {
+    pub boundary: ServiceBoundary,
+    pub stop_number: String,
+    pub routes: Vec<String>,
+}
+
+pub struct ChoiceCommand {
+    pub choice: usize,
 }
 
 pub struct SettingsClockCommand;
 
+pub struct SettingsFormatCommand {
+    pub format: TimesFormat,
+}
+
+pub struct SettingsCountdownCommand {
+    pub horizon: Option<i32>,
+}
+
+pub struct SettingsAccentsCommand {
+    pub accents: bool,
+}
+
+pub struct MoreCommand;
+
 pub struct HelpCommand;
 
 pub struct UnknownCommand;
@@ -117,7 +454,7 @@ mod tests {
         let command = parse_command("10619 16 18 BLUE");
         match command {
             Command::Times(times_command) => {
-                assert_eq!(times_command.stop_number, "10619");
+                assert_eq!(times_command.stop_numbers, vec!["10619"]);
                 assert_eq!(times_command.routes, vec!["16", "18", "BLUE"]);
             }
             _ => panic!("Expected TimesCommand"),
@@ -126,7 +463,7 @@ mod tests {
         let command_with_whitespace = parse_command(" 10064 ");
         match command_with_whitespace {
             Command::Times(times_command) => {
-                assert_eq!(times_command.stop_number, "10064");
+                assert_eq!(times_command.stop_numbers, vec!["10064"]);
                 assert_eq!(times_command.routes, Vec::<String>::new());
             }
             _ => panic!("Expected TimesCommand"),
@@ -135,11 +472,40 @@ mod tests {
         let command_with_optional_prefix = parse_command("times 10064");
         match command_with_optional_prefix {
             Command::Times(times_command) => {
-                assert_eq!(times_command.stop_number, "10064");
+                assert_eq!(times_command.stop_numbers, vec!["10064"]);
                 assert_eq!(times_command.routes, Vec::<String>::new());
             }
             _ => panic!("Expected TimesCommand"),
         }
+
+        let command_with_destination = parse_command("10619 16 to St Vital");
+        match command_with_destination {
+            Command::Times(times_command) => {
+                assert_eq!(times_command.routes, vec!["16"]);
+                assert_eq!(times_command.destination, Some("St Vital".to_string()));
+                assert_eq!(times_command.direction, None);
+            }
+            _ => panic!("Expected TimesCommand"),
+        }
+
+        let command_with_direction = parse_command("10619 16 North");
+        match command_with_direction {
+            Command::Times(times_command) => {
+                assert_eq!(times_command.routes, vec!["16"]);
+                assert_eq!(times_command.destination, None);
+                assert_eq!(times_command.direction, Some(Direction::North));
+            }
+            _ => panic!("Expected TimesCommand"),
+        }
+
+        let command_with_multiple_stops = parse_command("10619 10620 16");
+        match command_with_multiple_stops {
+            Command::Times(times_command) => {
+                assert_eq!(times_command.stop_numbers, vec!["10619", "10620"]);
+                assert_eq!(times_command.routes, vec!["16"]);
+            }
+            _ => panic!("Expected TimesCommand"),
+        }
     }
 
     #[test]
@@ -164,9 +530,185 @@ mod tests {
         match command_with_extra_spaces {
             Command::Stops(stops_command) => {
                 assert_eq!(stops_command.location, "245 smith");
+                assert_eq!(stops_command.routes, Vec::<String>::new());
+            }
+            _ => panic!("Expected StopsCommand"),
+        }
+
+        let command_with_routes = parse_command("stops 245 smith route 16 BLUE");
+        match command_with_routes {
+            Command::Stops(stops_command) => {
+                assert_eq!(stops_command.location, "245 smith");
+                assert_eq!(stops_command.routes, vec!["16", "BLUE"]);
+            }
+            _ => panic!("Expected StopsCommand"),
+        }
+
+        let command_with_coordinates = parse_command("stops 49.89218, -97.14084 16");
+        match command_with_coordinates {
+            Command::Stops(stops_command) => {
+                assert_eq!(stops_command.coordinates, Some((49.89218, -97.14084)));
+                assert_eq!(stops_command.routes, vec!["16"]);
+            }
+            _ => panic!("Expected StopsCommand"),
+        }
+
+        let command_with_on = parse_command("stops union station on 38");
+        match command_with_on {
+            Command::Stops(stops_command) => {
+                assert_eq!(stops_command.location, "union station");
+                assert_eq!(stops_command.routes, vec!["38"]);
+            }
+            _ => panic!("Expected StopsCommand"),
+        }
+
+        let command_with_a_numbered_place = parse_command("stops highway 59");
+        match command_with_a_numbered_place {
+            Command::Stops(stops_command) => {
+                assert_eq!(stops_command.location, "highway 59");
+                assert_eq!(stops_command.routes, Vec::<String>::new());
+            }
+            _ => panic!("Expected StopsCommand"),
+        }
+
+        let command_with_a_numbered_place_and_route = parse_command("stops highway 59 on 16");
+        match command_with_a_numbered_place_and_route {
+            Command::Stops(stops_command) => {
+                assert_eq!(stops_command.location, "highway 59");
+                assert_eq!(stops_command.routes, vec!["16"]);
             }
             _ => panic!("Expected StopsCommand"),
         }
+
+        let command_with_numbered_place = parse_command("stops 1,2 portage");
+        match command_with_numbered_place {
+            Command::Stops(stops_command) => {
+                assert_eq!(stops_command.location, "1,2 portage");
+                assert_eq!(stops_command.coordinates, None);
+            }
+            _ => panic!("Expected StopsCommand"),
+        }
+
+        let command_with_only_a_number = parse_command("stops 245");
+        match command_with_only_a_number {
+            Command::Stops(stops_command) => {
+                assert_eq!(stops_command.location, "245");
+                assert_eq!(stops_command.routes, Vec::<String>::new());
+            }
+            _ => panic!("Expected StopsCommand"),
+        }
+    }
+
+    #[test]
+    fn test_parse_coordinates() {
+        assert_eq!(parse_coordinates("49.89,-97.14"), Some((49.89, -97.14)));
+        assert_eq!(
+            parse_coordinates("geo:49.89,-97.14;u=35"),
+            Some((49.89, -97.14))
+        );
+        assert_eq!(
+            parse_coordinates("https://www.google.com/maps/@49.8921,-97.1408,17z"),
+            Some((49.8921, -97.1408))
+        );
+        assert_eq!(
+            parse_coordinates("https://maps.google.com/?q=49.8921,-97.1408"),
+            Some((49.8921, -97.1408))
+        );
+        assert_eq!(
+            parse_coordinates("https://www.google.com/maps/search/?api=1&query=49.8921%2C-97.1408"),
+            Some((49.8921, -97.1408))
+        );
+        assert_eq!(
+            parse_coordinates("https://maps.apple.com/?ll=49.8921,-97.1408&q=Dropped%20Pin"),
+            Some((49.8921, -97.1408))
+        );
+        assert_eq!(
+            parse_coordinates("https://maps.apple.com/?q=Dropped%20Pin&ll=49.8921,-97.1408"),
+            Some((49.8921, -97.1408))
+        );
+        assert_eq!(parse_coordinates("245 smith"), None);
+        assert_eq!(
+            parse_coordinates("https://example.com/?q=49.89,-97.14"),
+            None
+        );
+        assert_eq!(parse_coordinates("149.89,-97.14"), None);
+        assert_eq!(parse_coordinates("1,2 portage"), None);
+        assert_eq!(
+            parse_coordinates("geo:0,0?q=49.89,-97.14"),
+            Some((49.89, -97.14))
+        );
+        assert_eq!(
+            parse_coordinates("geo:0,0?q=49.89,-97.14(Union%20Station)"),
+            Some((49.89, -97.14))
+        );
+    }
+
+    #[test]
+    fn test_parse_near_command() {
+        let command = parse_command("16 near 245 Smith");
+        match command {
+            Command::Near(near_command) => {
+                assert_eq!(near_command.routes, vec!["16"]);
+                assert_eq!(near_command.location, "245 Smith");
+            }
+            _ => panic!("Expected NearCommand"),
+        }
+
+        let command_with_several_routes = parse_command("BLUE 16 Near union station");
+        match command_with_several_routes {
+            Command::Near(near_command) => {
+                assert_eq!(near_command.routes, vec!["blue", "16"]);
+                assert_eq!(near_command.location, "union station");
+            }
+            _ => panic!("Expected NearCommand"),
+        }
+    }
+
+    #[test]
+    fn test_parse_service_command() {
+        let command = parse_command("Last 10619 16 BLUE");
+        match command {
+            Command::Service(service_command) => {
+                assert_eq!(service_command.boundary, ServiceBoundary::Last);
+                assert_eq!(service_command.stop_number, "10619");
+                assert_eq!(service_command.routes, vec!["16", "BLUE"]);
+            }
+            _ => panic!("Expected ServiceCommand"),
+        }
+
+        let first_command = parse_command("first 10619");
+        match first_command {
+            Command::Service(service_command) => {
+                assert_eq!(service_command.boundary, ServiceBoundary::First);
+                assert_eq!(service_command.routes, Vec::<String>::new());
+            }
+            _ => panic!("Expected ServiceCommand"),
+        }
+    }
+
+    #[test]
+    fn test_parse_choice_command() {
+        let command = parse_command(" 3 ");
+        match command {
+            Command::Choice(choice_command) => {
+                assert_eq!(choice_command.choice, 3);
+            }
+            _ => panic!("Expected ChoiceCommand"),
+        }
+
+        let command_with_two_digits = parse_command("10");
+        match command_with_two_digits {
+            Command::Choice(choice_command) => {
+                assert_eq!(choice_command.choice, 10);
+            }
+            _ => panic!("Expected ChoiceCommand"),
+        }
+
+        let command_with_three_digits = parse_command("100");
+        match command_with_three_digits {
+            Command::Unknown(_) => (),
+            _ => panic!("Expected UnknownCommand from three digits"),
+        }
     }
 
     #[test]
@@ -190,6 +732,109 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_settings_format_command() {
+        let command = parse_command("Settings Format Grouped");
+        match command {
+            Command::SettingsFormat(settings_format_command) => {
+                assert_eq!(settings_format_command.format, TimesFormat::Grouped);
+            }
+            _ => panic!("Expected SettingsFormatCommand"),
+        }
+
+        let command_for_lines = parse_command("settings format lines");
+        match command_for_lines {
+            Command::SettingsFormat(settings_format_command) => {
+                assert_eq!(settings_format_command.format, TimesFormat::Lines);
+            }
+            _ => panic!("Expected SettingsFormatCommand"),
+        }
+
+        let command_for_headway = parse_command("settings format headway");
+        match command_for_headway {
+            Command::SettingsFormat(settings_format_command) => {
+                assert_eq!(settings_format_command.format, TimesFormat::Headway);
+            }
+            _ => panic!("Expected SettingsFormatCommand"),
+        }
+
+        let command_with_unknown_format = parse_command("settings format fancy");
+        match command_with_unknown_format {
+            Command::Unknown(_) => (),
+            _ => panic!("Expected UnknownCommand from unknown format"),
+        }
+    }
+
+    #[test]
+    fn test_parse_settings_countdown_command() {
+        let command = parse_command("settings countdown");
+        match command {
+            Command::SettingsCountdown(settings_countdown_command) => {
+                assert_eq!(
+                    settings_countdown_command.horizon,
+                    Some(DEFAULT_COUNTDOWN_HORIZON)
+                );
+            }
+            _ => panic!("Expected SettingsCountdownCommand"),
+        }
+
+        let command_with_horizon = parse_command("settings countdown 20");
+        match command_with_horizon {
+            Command::SettingsCountdown(settings_countdown_command) => {
+                assert_eq!(settings_countdown_command.horizon, Some(20));
+            }
+            _ => panic!("Expected SettingsCountdownCommand"),
+        }
+
+        let command_to_disable = parse_command("Settings Countdown OFF");
+        match command_to_disable {
+            Command::SettingsCountdown(settings_countdown_command) => {
+                assert_eq!(settings_countdown_command.horizon, None);
+            }
+            _ => panic!("Expected SettingsCountdownCommand"),
+        }
+    }
+
+    #[test]
+    fn test_parse_settings_accents_command() {
+        let command = parse_command("settings accents on");
+        match command {
+            Command::SettingsAccents(settings_accents_command) => {
+                assert!(settings_accents_command.accents);
+            }
+            _ => panic!("Expected SettingsAccentsCommand"),
+        }
+
+        let command_to_disable = parse_command("Settings Accents OFF");
+        match command_to_disable {
+            Command::SettingsAccents(settings_accents_command) => {
+                assert!(!settings_accents_command.accents);
+            }
+            _ => panic!("Expected SettingsAccentsCommand"),
+        }
+
+        let command_without_choice = parse_command("settings accents");
+        match command_without_choice {
+            Command::Unknown(_) => (),
+            _ => panic!("Expected UnknownCommand without on or off"),
+        }
+    }
+
+    #[test]
+    fn test_parse_more_command() {
+        let command = parse_command("More");
+        match command {
+            Command::More(_) => (),
+            _ => panic!("Expected MoreCommand"),
+        }
+
+        let command_with_extra_words = parse_command("more please");
+        match command_with_extra_words {
+            Command::Unknown(_) => (),
+            _ => panic!("Expected UnknownCommand from more with extra words"),
+        }
+    }
+
     #[test]
     fn test_parse_unknown_command() {
         let command = parse_command("unknown command");
@@ -1,6 +1,11 @@
-use crate::models::Number;
+use crate::{
+    commands::{SettingsAccentsCommand, SettingsCountdownCommand, SettingsFormatCommand},
+    models::{Number, TimesFormat},
+};
 use sqlx::PgPool;
 
+pub const DEFAULT_COUNTDOWN_HORIZON: i32 = 10;
+
 pub async fn handle_settings_clock_request(
     db: &PgPool,
     number: &Option<Number>,
@@ -33,3 +38,88 @@ pub async fn handle_settings_clock_request(
 
     Ok(response_text)
 }
+
+pub async fn handle_settings_format_request(
+    command: SettingsFormatCommand,
+    db: &PgPool,
+    number: &Option<Number>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let response_text = if let Some(number) = number {
+        sqlx::query(
+            "UPDATE numbers
+            SET times_format = $1
+            WHERE number = $2",
+        )
+        .bind(command.format)
+        .bind(&number.number)
+        .execute(db)
+        .await?;
+
+        match command.format {
+            TimesFormat::Lines => "times will now be one departure per line",
+            TimesFormat::Grouped => "times will now be grouped by route",
+            TimesFormat::Headway => "times for frequent routes will now be summarised",
+        }
+        .to_string()
+    } else {
+        "Cannot change settings with this interface".to_string()
+    };
+
+    Ok(response_text)
+}
+
+pub async fn handle_settings_countdown_request(
+    command: SettingsCountdownCommand,
+    db: &PgPool,
+    number: &Option<Number>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let response_text = if let Some(number) = number {
+        sqlx::query(
+            "UPDATE numbers
+            SET countdown_horizon = $1
+            WHERE number = $2",
+        )
+        .bind(command.horizon)
+        .bind(&number.number)
+        .execute(db)
+        .await?;
+
+        match command.horizon {
+            Some(horizon) => format!("times within {}min will now be a countdown", horizon),
+            None => "times will now always be clock times".to_string(),
+        }
+    } else {
+        "Cannot change settings with this interface".to_string()
+    };
+
+    Ok(response_text)
+}
+
+pub async fn handle_settings_accents_request(
+    command: SettingsAccentsCommand,
+    db: &PgPool,
+    number: &Option<Number>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let response_text = if let Some(number) = number {
+        sqlx::query(
+            "UPDATE numbers
+            SET accents = $1
+            WHERE number = $2",
+        )
+        .bind(command.accents)
+        .bind(&number.number)
+        .execute(db)
+        .await?;
+
+        if command.accents {
+            "replies will now keep accents and symbols"
+        } else {
+            "replies will now use plain characters so more fits in each text"
+        }
+        .to_string()
+    } else {
+        "Cannot change settings with this interface".to_string()
+    };
+
+    Ok(response_text)
+}
@@ -1,13 +1,27 @@
 use chrono::Local;
 
+use futures::future::join_all;
 use serde::Deserialize;
 use serde_json::{Number, Value};
 use sqlx::{types::Uuid, PgPool};
+use url::Url;
 
-use crate::{commands::StopsCommand, config::Config, odws::fetch_from_odws};
+use crate::{
+    aliases::find_alias,
+    commands::{paginate, StopsCommand},
+    config::Config,
+    conversation::{set_conversation_state, ConversationState, LocationChoice},
+    models::{self, Alias},
+    odws::fetch_from_odws,
+};
 
 const STOPS_DISTANCE: usize = 500;
+const ROUTE_FILTER_DISTANCES: [usize; 3] = [STOPS_DISTANCE, 1000, 2000];
 const MAXIMUM_STOPS_TO_RETURN: usize = 10;
+const MAXIMUM_LOCATION_CHOICES: usize = 5;
+const DUPLICATE_LOCATION_DISTANCE: f64 = 10.0;
+pub(super) const UNREADABLE_LINK_MESSAGE: &str =
+    "Couldn't read a location from that link, send an address or the coordinates instead";
 
 pub async fn handle_stops_request(
     command: StopsCommand,
@@ -15,41 +29,511 @@ pub async fn handle_stops_request(
     winnipeg_transit_api_address: String,
     maybe_incoming_message_id: Option<Uuid>,
     db: &PgPool,
+    number: &Option<models::Number>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let location = if let Some((latitude, longitude)) = command.coordinates {
+        LocationChoice {
+            name: format!("{},{}", latitude, longitude),
+            latitude: Number::from_f64(latitude).unwrap(),
+            longitude: Number::from_f64(longitude).unwrap(),
+        }
+    } else {
+        match locate(
+            &command.location,
+            config,
+            winnipeg_transit_api_address.clone(),
+            maybe_incoming_message_id,
+            db,
+        )
+        .await
+        {
+            Located::Found(location) => location,
+            Located::Ambiguous(locations) => {
+                return offer_location_choices(
+                    &command.location,
+                    &command.routes,
+                    false,
+                    locations,
+                    config,
+                    db,
+                    number,
+                )
+                .await
+            }
+            Located::NotFound => {
+                return Ok(format!("No locations found for {}", command.location).to_string())
+            }
+            Located::UnreadableLink => return Ok(UNREADABLE_LINK_MESSAGE.to_string()),
+        }
+    };
+
+    handle_stops_near_location(
+        location,
+        &command.routes,
+        config,
+        winnipeg_transit_api_address,
+        maybe_incoming_message_id,
+        db,
+        number,
+    )
+    .await
+}
+
+pub(super) async fn handle_stops_near_location(
+    location: LocationChoice,
+    routes: &[String],
+    config: &Config,
+    winnipeg_transit_api_address: String,
+    maybe_incoming_message_id: Option<Uuid>,
+    db: &PgPool,
+    number: &Option<models::Number>,
 ) -> Result<String, Box<dyn std::error::Error>> {
     let effective_on_string = Local::now().format("%Y-%m-%d").to_string();
 
+    let LocationChoice {
+        name: location_name,
+        latitude,
+        longitude,
+    } = location;
+
+    let (search_distance, stops) = find_nearby_stops(
+        &latitude,
+        &longitude,
+        routes,
+        config,
+        winnipeg_transit_api_address.clone(),
+        maybe_incoming_message_id,
+        db,
+    )
+    .await?;
+
+    let any_stops_found = !stops.is_empty();
+
+    let listed_stops: Vec<&Stop> = stops.iter().take(MAXIMUM_STOPS_TO_RETURN).collect();
+
+    let listed_stop_routes = join_all(listed_stops.iter().map(|stop| {
+        let stop_routes = fetch_stop_routes(
+            stop,
+            &effective_on_string,
+            config,
+            winnipeg_transit_api_address.clone(),
+            maybe_incoming_message_id,
+            db,
+        );
+
+        async { stop_routes.await.map_err(|e| e.to_string()) }
+    }))
+    .await;
+
+    let mut stop_entries: Vec<String> = Vec::new();
+    let mut listed_stop_numbers: Vec<u64> = Vec::new();
+
+    for (stop, stop_routes) in listed_stops.into_iter().zip(listed_stop_routes) {
+        let stop_routes = stop_routes?;
+
+        if stop_routes.is_empty() {
+            continue;
+        }
+
+        listed_stop_numbers.push(stop.number);
+
+        stop_entries.push(format!(
+            "{}. {} {} {}m {}",
+            listed_stop_numbers.len(),
+            stop.number,
+            stop.name_with_direction(),
+            stop.distance().round(),
+            stop_routes.join(" ")
+        ));
+    }
+
+    if stop_entries.is_empty() && !routes.is_empty() {
+        return Ok(format!(
+            "No stops for {} found within {}m of {}",
+            routes.join(" "),
+            search_distance,
+            location_name
+        ));
+    }
+
+    if !any_stops_found {
+        return Ok(format!(
+            "No stops found within {}m of {}",
+            STOPS_DISTANCE, location_name
+        )
+        .to_string());
+    }
+
+    let response = if routes.is_empty() {
+        format!("Stops near {}\n", location_name)
+    } else {
+        format!(
+            "Stops near {} for {} within {}m\n",
+            location_name,
+            routes.join(" "),
+            search_distance
+        )
+    };
+
+    if let Some(number) = number {
+        set_conversation_state(
+            db,
+            &number.number,
+            ConversationState::Stops {
+                stop_numbers: listed_stop_numbers,
+            },
+        )
+        .await?;
+    }
+
+    paginate(response, stop_entries, config, db, number).await
+}
+
+// Stops serving any of the routes, closest in each direction first. ODWS filters by route, so every
+// stop within each distance is considered, and the search widens until some are found.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn find_nearby_stops(
+    latitude: &Number,
+    longitude: &Number,
+    routes: &[String],
+    config: &Config,
+    winnipeg_transit_api_address: String,
+    maybe_incoming_message_id: Option<Uuid>,
+    db: &PgPool,
+) -> Result<(usize, Vec<Stop>), Box<dyn std::error::Error>> {
+    let search_distances: &[usize] = if routes.is_empty() {
+        &[STOPS_DISTANCE]
+    } else {
+        &ROUTE_FILTER_DISTANCES
+    };
+
+    let route_filters: Vec<Option<&str>> = if routes.is_empty() {
+        vec![None]
+    } else {
+        routes.iter().map(|route| Some(route.as_str())).collect()
+    };
+
+    let mut search_distance = STOPS_DISTANCE;
+    let mut stops: Vec<Stop> = Vec::new();
+
+    for &distance in search_distances {
+        search_distance = distance;
+
+        for route_stops in join_all(route_filters.iter().map(|route| {
+            let nearby_stops = fetch_nearby_stops(
+                latitude,
+                longitude,
+                distance,
+                *route,
+                config,
+                winnipeg_transit_api_address.clone(),
+                maybe_incoming_message_id,
+                db,
+            );
+
+            async { nearby_stops.await.map_err(|e| e.to_string()) }
+        }))
+        .await
+        {
+            for stop in route_stops? {
+                if !stops.iter().any(|existing| existing.number == stop.number) {
+                    stops.push(stop);
+                }
+            }
+        }
+
+        if !stops.is_empty() {
+            break;
+        }
+    }
+
+    Ok((search_distance, order_by_direction(stops)))
+}
+
+pub(super) async fn offer_location_choices(
+    query: &str,
+    routes: &[String],
+    near: bool,
+    locations: Vec<LocationChoice>,
+    config: &Config,
+    db: &PgPool,
+    number: &Option<models::Number>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let location_entries: Vec<String> = locations
+        .iter()
+        .enumerate()
+        .map(|(index, location)| format!("{}. {}", index + 1, location.name))
+        .collect();
+
+    let response = if let Some(number) = number {
+        set_conversation_state(
+            db,
+            &number.number,
+            ConversationState::Locations {
+                routes: routes.to_vec(),
+                locations,
+                near,
+            },
+        )
+        .await?;
+
+        format!(
+            "Several places match {}, reply with a number or be more specific\n",
+            query
+        )
+    } else {
+        format!("Several places match {}, be more specific\n", query)
+    };
+
+    paginate(response, location_entries, config, db, number).await
+}
+
+// A stop’s routes, numbered routes in order after named ones like BLUE
+async fn fetch_stop_routes(
+    stop: &Stop,
+    effective_on_string: &str,
+    config: &Config,
+    winnipeg_transit_api_address: String,
+    maybe_incoming_message_id: Option<Uuid>,
+    db: &PgPool,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let routes_query = format!(
+        "/v4/routes.json?stop={}&effective-on={}",
+        stop.number, effective_on_string
+    );
+
+    log::trace!("routes URL: {}", routes_query);
+
+    let (_routes_response_status, routes_response_text) = fetch_from_odws(
+        routes_query,
+        config,
+        winnipeg_transit_api_address,
+        maybe_incoming_message_id,
+        db,
+    )
+    .await;
+
+    let routes_response: RoutesResponse = match serde_json::from_str(&routes_response_text) {
+        Ok(response) => response,
+        Err(err) => {
+            log::error!(
+                "Error parsing routes response for stop {}: {}",
+                stop.number,
+                err
+            );
+            log::error!("Response: {}", routes_response_text);
+            return Err(Box::new(err));
+        }
+    };
+
+    let mut routes: Vec<String> = routes_response
+        .routes
+        .iter()
+        .map(|route| match &route.number {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            _ => panic!("Unexpected type parsing route number"),
+        })
+        .collect();
+
+    routes.sort_by(|a, b| {
+        let a_is_numeric = a.chars().all(char::is_numeric);
+        let b_is_numeric = b.chars().all(char::is_numeric);
+
+        if a_is_numeric && b_is_numeric {
+            a.parse::<u64>().unwrap().cmp(&b.parse::<u64>().unwrap())
+        } else if a_is_numeric {
+            std::cmp::Ordering::Greater
+        } else if b_is_numeric {
+            std::cmp::Ordering::Less
+        } else {
+            a.cmp(b)
+        }
+    });
+
+    Ok(routes)
+}
+
+pub(super) enum Located {
+    Found(LocationChoice),
+    Ambiguous(Vec<LocationChoice>),
+    NotFound,
+    UnreadableLink,
+}
+
+pub(super) async fn locate(
+    location: &str,
+    config: &Config,
+    winnipeg_transit_api_address: String,
+    maybe_incoming_message_id: Option<Uuid>,
+    db: &PgPool,
+) -> Located {
+    let alias = find_alias(db, location).await.unwrap_or_else(|e| {
+        log::error!("Failed to find alias: {}", e);
+        None
+    });
+
+    let location = match &alias {
+        Some(Alias {
+            name,
+            latitude: Some(latitude),
+            longitude: Some(longitude),
+            ..
+        }) => {
+            return Located::Found(LocationChoice {
+                name: name.clone(),
+                latitude: Number::from_f64(*latitude).unwrap(),
+                longitude: Number::from_f64(*longitude).unwrap(),
+            })
+        }
+        Some(Alias {
+            query: Some(query), ..
+        }) => query.as_str(),
+        _ => location,
+    };
+
+    // Links without coordinates, like shortened maps links, mean nothing to a locations search
+    if Url::parse(location).is_ok_and(|url| ["http", "https"].contains(&url.scheme())) {
+        return Located::UnreadableLink;
+    }
+
+    let effective_on_string = Local::now().format("%Y-%m-%d").to_string();
+
     let locations_query = format!(
         "/v4/locations:{}.json?usage=short&effective-on={}",
-        command.location, effective_on_string
+        location, effective_on_string
     );
     log::trace!("locations URL: {}", locations_query);
 
     let (_locations_response_status, locations_response_text) = fetch_from_odws(
         locations_query,
         config,
-        winnipeg_transit_api_address.clone(),
+        winnipeg_transit_api_address,
         maybe_incoming_message_id,
         db,
     )
     .await;
 
-    let (location_name, latitude, longitude) =
-        match extract_location_details(&locations_response_text) {
-            Ok(details) => details,
-            Err(_) => return Ok(format!("No locations found for {}", command.location).to_string()),
-        };
+    let candidates = match extract_location_candidates(&locations_response_text) {
+        Ok(candidates) => candidates,
+        Err(_) => return Located::NotFound,
+    };
+
+    if let Some(location) = preferred_location(location, &candidates) {
+        return Located::Found(location);
+    }
+
+    let mut locations = distinct_locations(candidates);
+
+    if locations.len() > 1 {
+        Located::Ambiguous(locations)
+    } else {
+        Located::Found(locations.remove(0))
+    }
+}
+
+// The result named what was asked for, otherwise the choice is left to the asker
+fn preferred_location(
+    query: &str,
+    candidates: &[(&'static str, LocationChoice)],
+) -> Option<LocationChoice> {
+    candidates
+        .iter()
+        .find(|(_, candidate)| names_match(query, &candidate.name))
+        .map(|(_, named)| named.clone())
+}
+
+const STREET_TYPE_ABBREVIATIONS: [&str; 12] = [
+    "st", "ave", "rd", "blvd", "dr", "cres", "pl", "bay", "way", "ln", "hwy", "pkwy",
+];
+
+// ODWS names run words together and abbreviate the street type, which queries often leave off
+fn names_match(query: &str, name: &str) -> bool {
+    let normalise = |text: &str| -> String {
+        text.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+
+    let query = normalise(query);
+    let name = normalise(name);
+
+    name == query
+        || name.strip_prefix(&query).is_some_and(|street_type| {
+            !query.is_empty() && STREET_TYPE_ABBREVIATIONS.contains(&street_type)
+        })
+}
+
+// Duplicates share a type and either a name or a spot, like the legs of one intersection
+fn distinct_locations(candidates: Vec<(&'static str, LocationChoice)>) -> Vec<LocationChoice> {
+    let mut distinct: Vec<(&'static str, LocationChoice)> = Vec::new();
+
+    for (kind, candidate) in candidates {
+        if distinct.len() == MAXIMUM_LOCATION_CHOICES {
+            break;
+        }
+
+        if distinct.iter().any(|(distinct_kind, location)| {
+            *distinct_kind == kind
+                && (location.name == candidate.name
+                    || distance_between(location, &candidate) < DUPLICATE_LOCATION_DISTANCE)
+        }) {
+            continue;
+        }
+
+        distinct.push((kind, candidate));
+    }
+
+    distinct.into_iter().map(|(_, location)| location).collect()
+}
+
+// Great-circle distance in metres
+fn distance_between(a: &LocationChoice, b: &LocationChoice) -> f64 {
+    const EARTH_RADIUS: f64 = 6_371_000.0;
+
+    let a_latitude = a.latitude.as_f64().unwrap_or_default().to_radians();
+    let b_latitude = b.latitude.as_f64().unwrap_or_default().to_radians();
+    let latitude_delta = b_latitude - a_latitude;
+    let longitude_delta = (b.longitude.as_f64().unwrap_or_default()
+        - a.longitude.as_f64().unwrap_or_default())
+    .to_radians();
+
+    let haversine = (latitude_delta / 2.0).sin().powi(2)
+        + a_latitude.cos() * b_latitude.cos() * (longitude_delta / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS * haversine.sqrt().asin()
+}
 
-    let stops_query = format!(
-        "/v4/stops.json?lat={}&lon={}&distance={}&usage=short&effective-on={}",
-        latitude, longitude, STOPS_DISTANCE, effective_on_string
+// Stops within a distance in metres, optionally only those served by a route
+#[allow(clippy::too_many_arguments)]
+async fn fetch_nearby_stops(
+    latitude: &Number,
+    longitude: &Number,
+    distance: usize,
+    route: Option<&str>,
+    config: &Config,
+    winnipeg_transit_api_address: String,
+    maybe_incoming_message_id: Option<Uuid>,
+    db: &PgPool,
+) -> Result<Vec<Stop>, Box<dyn std::error::Error>> {
+    let effective_on_string = Local::now().format("%Y-%m-%d").to_string();
+
+    let mut stops_query = format!(
+        "/v4/stops.json?lat={}&lon={}&distance={}&walking=true&usage=short&effective-on={}",
+        latitude, longitude, distance, effective_on_string
     );
 
+    if let Some(route) = route {
+        stops_query.push_str(&format!("&route={}", route));
+    }
+
     log::trace!("stops URL: {}", stops_query);
 
     let (_stops_response_status, stops_response_text) = fetch_from_odws(
         stops_query,
         config,
-        winnipeg_transit_api_address.clone(),
+        winnipeg_transit_api_address,
         maybe_incoming_message_id,
         db,
     )
@@ -64,88 +548,41 @@ pub async fn handle_stops_request(
         }
     };
 
-    if stops_response.stops.is_empty() {
-        return Ok(format!(
-            "No stops found within {}m of {}",
-            STOPS_DISTANCE, location_name
-        )
-        .to_string());
-    }
-
-    let mut response = format!("Stops near {}\n", location_name);
-
-    for stop in stops_response.stops.iter().take(MAXIMUM_STOPS_TO_RETURN) {
-        let routes_query = format!(
-            "/v4/routes.json?stop={}&effective-on={}",
-            stop.number, effective_on_string
-        );
-
-        log::trace!("routes URL: {}", routes_query);
-
-        let (_routes_response_status, routes_response_text) = fetch_from_odws(
-            routes_query,
-            config,
-            winnipeg_transit_api_address.clone(),
-            maybe_incoming_message_id,
-            db,
-        )
-        .await;
-
-        let routes_response: RoutesResponse = match serde_json::from_str(&routes_response_text) {
-            Ok(response) => response,
-            Err(err) => {
-                log::error!(
-                    "Error parsing routes response for stop {}: {}",
-                    stop.number,
-                    err
-                );
-                log::error!("Response: {}", routes_response_text);
-                return Err(Box::new(err));
-            }
-        };
+    Ok(stops_response.stops)
+}
 
-        if routes_response.routes.is_empty() {
-            continue;
+// The closest stop in each direction comes first, then the rest by distance
+fn order_by_direction(mut stops: Vec<Stop>) -> Vec<Stop> {
+    stops.sort_by(|a, b| a.distance().total_cmp(&b.distance()));
+
+    let mut seen_directions: Vec<Option<String>> = Vec::new();
+    let (closest, rest): (Vec<Stop>, Vec<Stop>) = stops.into_iter().partition(|stop| {
+        if seen_directions.contains(&stop.direction) {
+            false
+        } else {
+            seen_directions.push(stop.direction.clone());
+            true
         }
+    });
 
-        let mut routes: Vec<String> = routes_response
-            .routes
-            .iter()
-            .map(|route| match &route.number {
-                Value::String(s) => s.clone(),
-                Value::Number(n) => n.to_string(),
-                _ => panic!("Unexpected type parsing route number"),
-            })
-            .collect();
-
-        routes.sort_by(|a, b| {
-            let a_is_numeric = a.chars().all(char::is_numeric);
-            let b_is_numeric = b.chars().all(char::is_numeric);
-
-            if a_is_numeric && b_is_numeric {
-                a.parse::<u64>().unwrap().cmp(&b.parse::<u64>().unwrap())
-            } else if a_is_numeric {
-                std::cmp::Ordering::Greater
-            } else if b_is_numeric {
-                std::cmp::Ordering::Less
-            } else {
-                a.cmp(b)
-            }
-        });
-
-        response += &format!("\n{} {} {}\n", stop.number, stop.name, routes.join(" "));
-    }
+    closest.into_iter().chain(rest).collect()
+}
 
-    Ok(response)
+// ODWS directions like Northbound are shortened to NB
+pub(super) fn direction_abbreviation(direction: &str) -> String {
+    format!(
+        "{}B",
+        direction
+            .chars()
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase()
+    )
 }
 
-fn extract_location_details(
+fn extract_location_candidates(
     locations_response_text: &str,
-) -> Result<(String, Number, Number), Box<dyn std::error::Error>> {
-    let location_name;
-    let latitude;
-    let longitude;
-
+) -> Result<Vec<(&'static str, LocationChoice)>, Box<dyn std::error::Error>> {
     let locations_response: LocationResponse = match serde_json::from_str(locations_response_text) {
         Ok(response) => response,
         Err(err) => {
@@ -162,32 +599,50 @@ fn extract_location_details(
         )));
     }
 
-    match &locations_response.locations[0] {
-        Location::Address(address) => {
-            location_name = format!("{} {}", address.street_number, address.street.name);
-            latitude = address.centre.geographic.latitude.clone();
-            longitude = address.centre.geographic.longitude.clone();
-        }
-        Location::Intersection(intersection) => {
-            location_name = format!(
-                "{}@{}",
-                intersection.street.name, intersection.cross_street.name
-            );
-            latitude = intersection.centre.geographic.latitude.clone();
-            longitude = intersection.centre.geographic.longitude.clone();
-        }
+    Ok(locations_response
+        .locations
+        .iter()
+        .map(location_details)
+        .collect())
+}
+
+fn location_details(location: &Location) -> (&'static str, LocationChoice) {
+    match location {
+        Location::Address(address) => (
+            "address",
+            LocationChoice {
+                name: format!("{} {}", address.street_number, address.street.name),
+                latitude: address.centre.geographic.latitude.clone(),
+                longitude: address.centre.geographic.longitude.clone(),
+            },
+        ),
+        Location::Intersection(intersection) => (
+            "intersection",
+            LocationChoice {
+                name: format!(
+                    "{}@{}",
+                    intersection.street.name, intersection.cross_street.name
+                ),
+                latitude: intersection.centre.geographic.latitude.clone(),
+                longitude: intersection.centre.geographic.longitude.clone(),
+            },
+        ),
         Location::Monument(monument) => {
             let monument_address = format!(
                 "{} {}",
                 monument.address.street_number, monument.address.street.name
             );
-            location_name = format!("{} ({})", monument.name.clone(), monument_address);
-            latitude = monument.address.centre.geographic.latitude.clone();
-            longitude = monument.address.centre.geographic.longitude.clone();
+
+            (
+                "monument",
+                LocationChoice {
+                    name: format!("{} ({})", monument.name.clone(), monument_address),
+                    latitude: monument.address.centre.geographic.latitude.clone(),
+                    longitude: monument.address.centre.geographic.longitude.clone(),
+                },
+            )
         }
     }
-
-    Ok((location_name, latitude, longitude))
 }
 
 #[cfg(test)]
@@ -198,16 +653,17 @@ mod tests {
     fn test_extract_monument_details() {
         let locations_response_text = include_str!("../../tests/fixtures/stops/locations.json");
 
-        let result = extract_location_details(locations_response_text);
+        let result = extract_location_candidates(locations_response_text);
         assert!(result.is_ok());
 
-        let (location_name, latitude, longitude) = result.unwrap();
+        let (kind, location) = result.unwrap().remove(0);
+        assert_eq!(kind, "monument");
         assert_eq!(
-            location_name,
+            location.name,
             "Via Rail Station (Union Station) (123 MainSt)"
         );
-        assert_eq!(latitude.to_string(), "49.88895");
-        assert_eq!(longitude.to_string(), "-97.13424");
+        assert_eq!(location.latitude.to_string(), "49.88895");
+        assert_eq!(location.longitude.to_string(), "-97.13424");
     }
 
     #[test]
@@ -215,13 +671,14 @@ mod tests {
         let locations_response_text =
             include_str!("../../tests/fixtures/stops/locations-address.json");
 
-        let result = extract_location_details(locations_response_text);
+        let result = extract_location_candidates(locations_response_text);
         assert!(result.is_ok());
 
-        let (location_name, latitude, longitude) = result.unwrap();
-        assert_eq!(location_name, "245 SmithSt");
-        assert_eq!(latitude.to_string(), "49.89218");
-        assert_eq!(longitude.to_string(), "-97.14084");
+        let (kind, location) = result.unwrap().remove(0);
+        assert_eq!(kind, "address");
+        assert_eq!(location.name, "245 SmithSt");
+        assert_eq!(location.latitude.to_string(), "49.89218");
+        assert_eq!(location.longitude.to_string(), "-97.14084");
     }
 
     #[test]
@@ -229,13 +686,126 @@ mod tests {
         let locations_response_text =
             include_str!("../../tests/fixtures/stops/locations-intersection.json");
 
-        let result = extract_location_details(locations_response_text);
+        let result = extract_location_candidates(locations_response_text);
         assert!(result.is_ok());
 
-        let (location_name, latitude, longitude) = result.unwrap();
-        assert_eq!(location_name, "PortageAve@MainSt");
-        assert_eq!(latitude.to_string(), "49.89553");
-        assert_eq!(longitude.to_string(), "-97.13848");
+        let (kind, location) = result.unwrap().remove(0);
+        assert_eq!(kind, "intersection");
+        assert_eq!(location.name, "PortageAve@MainSt");
+        assert_eq!(location.latitude.to_string(), "49.89553");
+        assert_eq!(location.longitude.to_string(), "-97.13848");
+    }
+
+    #[test]
+    fn test_distinct_locations() {
+        let addresses = extract_location_candidates(include_str!(
+            "../../tests/fixtures/stops/locations-address.json"
+        ))
+        .unwrap();
+
+        let names: Vec<String> = distinct_locations(addresses.clone())
+            .into_iter()
+            .map(|location| location.name)
+            .collect();
+        assert_eq!(names, vec!["245 SmithSt", "245 SmithfieldAve"]);
+
+        let mut repeated_address = addresses.clone();
+        repeated_address.push(addresses[0].clone());
+
+        assert_eq!(distinct_locations(repeated_address).len(), 2);
+
+        // A monument at the same spot as an address is still a different choice
+        let mut monument_at_an_address =
+            extract_location_candidates(include_str!("../../tests/fixtures/stops/locations.json"))
+                .unwrap();
+        let mut monument_address = monument_at_an_address[0].1.clone();
+        monument_address.name = "123 MainSt".to_string();
+        monument_at_an_address.push(("address", monument_address));
+
+        assert_eq!(distinct_locations(monument_at_an_address).len(), 2);
+
+        let legs_of_one_intersection = extract_location_candidates(include_str!(
+            "../../tests/fixtures/stops/locations-intersection.json"
+        ))
+        .unwrap();
+
+        assert_eq!(distinct_locations(legs_of_one_intersection).len(), 1);
+    }
+
+    #[test]
+    fn test_preferred_location() {
+        let addresses = extract_location_candidates(include_str!(
+            "../../tests/fixtures/stops/locations-address.json"
+        ))
+        .unwrap();
+
+        assert_eq!(
+            preferred_location("245 smith", &addresses).map(|location| location.name),
+            Some("245 SmithSt".to_string())
+        );
+        assert_eq!(
+            preferred_location("245 smithfield ave", &addresses).map(|location| location.name),
+            Some("245 SmithfieldAve".to_string())
+        );
+        assert_eq!(preferred_location("245 sm", &addresses), None);
+
+        let mut address_and_monument =
+            extract_location_candidates(include_str!("../../tests/fixtures/stops/locations.json"))
+                .unwrap();
+        address_and_monument.push(addresses[1].clone());
+
+        assert_eq!(preferred_location("union", &address_and_monument), None);
+        assert_eq!(
+            distinct_locations(address_and_monument)
+                .into_iter()
+                .map(|location| location.name)
+                .collect::<Vec<String>>(),
+            vec![
+                "Via Rail Station (Union Station) (123 MainSt)",
+                "245 SmithfieldAve"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_order_by_direction() {
+        let stops_response_text = include_str!("../../tests/fixtures/stops/stops.json");
+        let stops_response: StopsResponse = serde_json::from_str(stops_response_text).unwrap();
+
+        let stop_numbers: Vec<u64> = order_by_direction(stops_response.stops)
+            .iter()
+            .take(6)
+            .map(|stop| stop.number)
+            .collect();
+
+        assert_eq!(stop_numbers, vec![10625, 10641, 11052, 10907, 11010, 10901]);
+    }
+
+    #[test]
+    fn test_name_with_direction() {
+        let stop = Stop {
+            number: 10625,
+            name: "NB Main@Broadway (Union Station)".to_string(),
+            direction: Some("Northbound".to_string()),
+            distances: None,
+        };
+
+        assert_eq!(
+            stop.name_with_direction(),
+            "NB Main@Broadway (Union Station)"
+        );
+
+        let stop_without_direction_in_name = Stop {
+            number: 10625,
+            name: "Main@Broadway".to_string(),
+            direction: Some("Southbound".to_string()),
+            distances: None,
+        };
+
+        assert_eq!(
+            stop_without_direction_in_name.name_with_direction(),
+            "SB Main@Broadway"
+        );
     }
 }
 
@@ -296,9 +866,42 @@ struct StopsResponse {
 }
 
 #[derive(Deserialize)]
-struct Stop {
-    number: u64,
+pub(super) struct Stop {
+    pub(super) number: u64,
     name: String,
+    pub(super) direction: Option<String>,
+    distances: Option<Distances>,
+}
+
+impl Stop {
+    // Walking distance in metres when ODWS could compute it, otherwise direct
+    fn distance(&self) -> f64 {
+        self.distances.as_ref().map_or(0.0, |distances| {
+            distances.walking.unwrap_or(distances.direct)
+        })
+    }
+
+    // Stop names usually start with their direction, like NB Main@Broadway
+    fn name_with_direction(&self) -> String {
+        match &self.direction {
+            Some(direction) => {
+                let abbreviation = direction_abbreviation(direction);
+
+                if self.name.starts_with(&format!("{} ", abbreviation)) {
+                    self.name.clone()
+                } else {
+                    format!("{} {}", abbreviation, self.name)
+                }
+            }
+            None => self.name.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Distances {
+    direct: f64,
+    walking: Option<f64>,
 }
 
 #[derive(Deserialize)]
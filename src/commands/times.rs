@@ -1,13 +1,32 @@
-use chrono::NaiveDateTime;
-use serde::Deserialize;
+use chrono::{Duration, NaiveDateTime};
+use futures::future::join_all;
+use serde::{Deserialize, Deserializer};
 use serde_json::Value;
 use sqlx::{types::Uuid, PgPool};
+use std::collections::HashMap;
+use url::form_urlencoded;
 
-use crate::{commands::TimesCommand, config::Config, models::Number, odws::fetch_from_odws};
+use crate::{
+    commands::{
+        fits_on_one_page, paginate, split_groups, Direction, ServiceBoundary, ServiceCommand,
+        TimesCommand,
+    },
+    config::Config,
+    models::{Number, TimesFormat},
+    odws::{fetch_from_odws, winnipeg_now},
+};
 
-const MAX_RESPONSE_LENGTH: usize = 140;
 const DELAY_THRESHOLD: i64 = 3;
 const AHEAD_THRESHOLD: i64 = 1;
+// Each stop is its own API request, so one text can’t fan out into many
+const MAXIMUM_STOPS: usize = 4;
+const MINIMUM_HEADWAY_DEPARTURES: usize = 3;
+const MAXIMUM_HEADWAY: i64 = 15;
+const MAXIMUM_HEADWAY_VARIATION: i64 = 3;
+
+// Service after midnight belongs to the previous day until this hour
+const SERVICE_DAY_START_HOUR: u32 = 4;
+const FIRST_SERVICE_WINDOW_HOURS: i64 = 12;
 
 pub async fn handle_times_request(
     command: TimesCommand,
@@ -17,9 +36,400 @@ pub async fn handle_times_request(
     db: &PgPool,
     number: &Option<Number>,
 ) -> Result<String, Box<dyn std::error::Error>> {
+    if command.stop_numbers.len() > MAXIMUM_STOPS {
+        return Ok(format!(
+            "Times can show up to {} stops at once, please send fewer",
+            MAXIMUM_STOPS
+        ));
+    }
+
+    let stop_schedules = join_all(command.stop_numbers.iter().map(|stop_number| {
+        fetch_stop_schedule(
+            stop_number,
+            "",
+            config,
+            winnipeg_transit_api_address.clone(),
+            maybe_incoming_message_id,
+            db,
+        )
+    }))
+    .await;
+
+    if let [None] = stop_schedules.as_slice() {
+        return Ok(format!(
+            "No schedule found for stop {}, does it exist?",
+            command.stop_numbers[0]
+        ));
+    }
+
+    let variant_directions: Vec<HashMap<String, Direction>> = if command.direction.is_some() {
+        join_all(stop_schedules.iter().map(|maybe_stop_schedule_response| {
+            fetch_variant_directions(
+                maybe_stop_schedule_response.as_ref(),
+                &command.routes,
+                config,
+                winnipeg_transit_api_address.clone(),
+                maybe_incoming_message_id,
+                db,
+            )
+        }))
+        .await
+    } else {
+        vec![HashMap::new(); stop_schedules.len()]
+    };
+
+    let label_lines_with_stop = command.stop_numbers.len() > 1;
+
+    let mut response_text = String::new();
+    let mut departures: Vec<Departure> = Vec::new();
+    let mut route_matched = false;
+
+    for ((stop_number, maybe_stop_schedule_response), variant_directions) in command
+        .stop_numbers
+        .iter()
+        .zip(&stop_schedules)
+        .zip(&variant_directions)
+    {
+        let stop_schedule_response = match maybe_stop_schedule_response {
+            Some(stop_schedule_response) => stop_schedule_response,
+            None => {
+                response_text.push_str(&format!(
+                    "No schedule found for stop {}, does it exist?\n",
+                    stop_number
+                ));
+                continue;
+            }
+        };
+
+        let stop = match &stop_schedule_response.stop_schedule.stop_data {
+            StopData::Single { stop } => stop,
+            StopData::Multiple { stop } => &stop[0],
+        };
+
+        response_text.push_str(&format!("{} {}\n", stop.number, stop.name));
+
+        let line_prefix = if label_lines_with_stop {
+            format!("{} ", stop.number)
+        } else {
+            String::new()
+        };
+
+        append_departures(
+            stop_schedule_response,
+            stop,
+            &command,
+            variant_directions,
+            &line_prefix,
+            &mut departures,
+            &mut route_matched,
+        );
+    }
+
+    let departures_found = !departures.is_empty();
+
+    let mut response_text =
+        render_departures(response_text, departures, config, db, number).await?;
+
+    let any_schedule_found = stop_schedules.iter().any(Option::is_some);
+
+    if !route_matched && any_schedule_found {
+        response_text.push_str(&format!(
+            "No routes found matching {} at {}",
+            command.routes.join(" "),
+            if label_lines_with_stop {
+                "these stops"
+            } else {
+                "this stop"
+            }
+        ));
+    } else if !departures_found
+        && any_schedule_found
+        && (command.destination.is_some() || command.direction.is_some())
+    {
+        let mut filters = command.routes.clone();
+
+        if let Some(destination) = &command.destination {
+            filters.push(format!("to {}", destination));
+        }
+
+        if let Some(direction) = command.direction {
+            filters.push(direction.name().to_string());
+        }
+
+        response_text.push_str(&format!(
+            "No departures found matching {}",
+            filters.join(" ")
+        ));
+    }
+
+    Ok(response_text)
+}
+
+// Orders departures by time and renders them in the number’s preferred format, a page at a time
+pub(super) async fn render_departures(
+    response_text: String,
+    mut departures: Vec<Departure>,
+    config: &Config,
+    db: &PgPool,
+    number: &Option<Number>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    departures.sort_by(|a, b| a.time.cmp(&b.time));
+
+    let time_display = TimeDisplay {
+        clock_format: clock_format(number),
+        countdown_horizon: number
+            .as_ref()
+            .and_then(|number| number.countdown_horizon)
+            .map(i64::from),
+    };
+
+    let times_format = number
+        .as_ref()
+        .map_or(TimesFormat::Lines, |number| number.times_format);
+
+    let mut schedule_entries = match times_format {
+        TimesFormat::Lines => render_lines(&departures, &time_display),
+        TimesFormat::Grouped => split_groups(
+            &response_text,
+            render_grouped(&departures, &time_display),
+            config,
+            number,
+        ),
+        TimesFormat::Headway => split_groups(
+            &response_text,
+            render_headway(&departures, &time_display),
+            config,
+            number,
+        ),
+    };
+
+    // Grouping is only worth its denser reading when it saves asking for more
+    if times_format == TimesFormat::Lines
+        && !fits_on_one_page(&response_text, &schedule_entries, config, number)
+    {
+        let grouped_entries = split_groups(
+            &response_text,
+            render_grouped(&departures, &time_display),
+            config,
+            number,
+        );
+
+        if fits_on_one_page(&response_text, &grouped_entries, config, number) {
+            schedule_entries = grouped_entries;
+        }
+    }
+
+    paginate(response_text, schedule_entries, config, db, number).await
+}
+
+// Departures of the given routes at one stop with each label prefixed, None when it has no schedule
+pub(super) async fn fetch_stop_departures(
+    stop_number: &str,
+    routes: &[String],
+    line_prefix: &str,
+    config: &Config,
+    winnipeg_transit_api_address: String,
+    maybe_incoming_message_id: Option<Uuid>,
+    db: &PgPool,
+) -> Option<Vec<Departure>> {
+    let stop_schedule_response = fetch_stop_schedule(
+        stop_number,
+        "",
+        config,
+        winnipeg_transit_api_address,
+        maybe_incoming_message_id,
+        db,
+    )
+    .await?;
+
+    let stop = match &stop_schedule_response.stop_schedule.stop_data {
+        StopData::Single { stop } => stop,
+        StopData::Multiple { stop } => &stop[0],
+    };
+
+    let times_command = TimesCommand {
+        stop_numbers: vec![stop_number.to_string()],
+        routes: routes.to_vec(),
+        destination: None,
+        direction: None,
+    };
+
+    let mut departures: Vec<Departure> = Vec::new();
+    let mut route_matched = false;
+
+    append_departures(
+        &stop_schedule_response,
+        stop,
+        &times_command,
+        &HashMap::new(),
+        line_prefix,
+        &mut departures,
+        &mut route_matched,
+    );
+
+    Some(departures)
+}
+
+pub async fn handle_service_request(
+    command: ServiceCommand,
+    config: &Config,
+    winnipeg_transit_api_address: String,
+    maybe_incoming_message_id: Option<Uuid>,
+    db: &PgPool,
+    number: &Option<Number>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let now = winnipeg_now();
+    let next_service_day_start = next_service_day_start(now);
+
+    let (start, end, heading) = match command.boundary {
+        ServiceBoundary::Last => (now, next_service_day_start, "Last"),
+        ServiceBoundary::First => (
+            next_service_day_start,
+            next_service_day_start + Duration::hours(FIRST_SERVICE_WINDOW_HOURS),
+            "First",
+        ),
+    };
+
+    let window = format!(
+        "&start={}&end={}",
+        start.format("%Y-%m-%dT%H:%M:%S"),
+        end.format("%Y-%m-%dT%H:%M:%S")
+    );
+
+    let stop_schedule_response = match fetch_stop_schedule(
+        &command.stop_number,
+        &window,
+        config,
+        winnipeg_transit_api_address,
+        maybe_incoming_message_id,
+        db,
+    )
+    .await
+    {
+        Some(stop_schedule_response) => stop_schedule_response,
+        None => {
+            return Ok(format!(
+                "No schedule found for stop {}, does it exist?",
+                command.stop_number
+            ))
+        }
+    };
+
+    let stop = match &stop_schedule_response.stop_schedule.stop_data {
+        StopData::Single { stop } => stop,
+        StopData::Multiple { stop } => &stop[0],
+    };
+
+    let times_command = TimesCommand {
+        stop_numbers: vec![command.stop_number.clone()],
+        routes: command.routes.clone(),
+        destination: None,
+        direction: None,
+    };
+
+    let mut departures: Vec<Departure> = Vec::new();
+    let mut route_matched = false;
+
+    append_departures(
+        &stop_schedule_response,
+        stop,
+        &times_command,
+        &HashMap::new(),
+        "",
+        &mut departures,
+        &mut route_matched,
+    );
+
+    // Keep one departure per route, skipping cancelled trips nobody can catch
+    let mut route_departures: Vec<Departure> = Vec::new();
+
+    for departure in departures.into_iter().filter(|d| !d.cancelled) {
+        match route_departures
+            .iter_mut()
+            .find(|existing| existing.route == departure.route)
+        {
+            Some(existing) => {
+                let replaces = match command.boundary {
+                    ServiceBoundary::Last => departure.time > existing.time,
+                    ServiceBoundary::First => departure.time < existing.time,
+                };
+
+                if replaces {
+                    *existing = departure;
+                }
+            }
+            None => route_departures.push(departure),
+        }
+    }
+
+    route_departures.sort_by(|a, b| a.time.cmp(&b.time));
+
+    if route_departures.is_empty() {
+        let routes = if command.routes.is_empty() {
+            String::new()
+        } else {
+            format!(" for {}", command.routes.join(" "))
+        };
+
+        return Ok(match command.boundary {
+            ServiceBoundary::Last => format!(
+                "No more departures today{} at {} {}",
+                routes, stop.number, stop.name
+            ),
+            ServiceBoundary::First => format!(
+                "No first departures found{} at {} {}",
+                routes, stop.number, stop.name
+            ),
+        });
+    }
+
+    let time_display = TimeDisplay {
+        clock_format: clock_format(number),
+        countdown_horizon: None,
+    };
+
+    paginate(
+        format!("{} buses at {} {}\n", heading, stop.number, stop.name),
+        render_lines(&route_departures, &time_display),
+        config,
+        db,
+        number,
+    )
+    .await
+}
+
+fn next_service_day_start(now: NaiveDateTime) -> NaiveDateTime {
+    let service_day_start = now
+        .date()
+        .and_hms_opt(SERVICE_DAY_START_HOUR, 0, 0)
+        .unwrap();
+
+    if now < service_day_start {
+        service_day_start
+    } else {
+        service_day_start + Duration::days(1)
+    }
+}
+
+fn clock_format(number: &Option<Number>) -> &'static str {
+    if number.is_some() && !number.as_ref().unwrap().twelve_hour {
+        "%H:%M"
+    } else {
+        "%-I:%M%p"
+    }
+}
+
+async fn fetch_stop_schedule(
+    stop_number: &str,
+    window: &str,
+    config: &Config,
+    winnipeg_transit_api_address: String,
+    maybe_incoming_message_id: Option<Uuid>,
+    db: &PgPool,
+) -> Option<StopScheduleResponse> {
     let query = format!(
-        "/v4/stops/{}/schedule.json?usage=short",
-        command.stop_number,
+        "/v4/stops/{}/schedule.json?usage=short{}",
+        stop_number, window
     );
 
     let (api_response_status, api_response_text) = fetch_from_odws(
@@ -32,66 +442,213 @@ pub async fn handle_times_request(
     .await;
 
     if !api_response_status.is_success() {
-        return Ok(format!(
-            "No schedule found for stop {}, does it exist?",
-            command.stop_number
-        ));
+        return None;
     }
 
     let parsed_response = serde_json::from_str::<StopScheduleResponse>(&api_response_text).unwrap();
 
-    let mut response_text = match &parsed_response.stop_schedule.stop_data {
-        StopData::Single { stop } => {
-            format!("{} {}\n", stop.number, stop.name)
+    Some(parsed_response)
+}
+
+// A stop can be served in both directions, so each variant’s direction comes from its own path
+async fn fetch_variant_directions(
+    maybe_stop_schedule_response: Option<&StopScheduleResponse>,
+    routes: &[String],
+    config: &Config,
+    winnipeg_transit_api_address: String,
+    maybe_incoming_message_id: Option<Uuid>,
+    db: &PgPool,
+) -> HashMap<String, Direction> {
+    let Some(stop_schedule_response) = maybe_stop_schedule_response else {
+        return HashMap::new();
+    };
+
+    let stop_number = match &stop_schedule_response.stop_schedule.stop_data {
+        StopData::Single { stop } => stop.number,
+        StopData::Multiple { stop } => stop[0].number,
+    };
+
+    let mut variant_keys: Vec<&str> = Vec::new();
+
+    for route_schedule in &stop_schedule_response.stop_schedule.route_schedules {
+        if !route_schedule.route.matches(routes) {
+            continue;
         }
-        StopData::Multiple { stop } => {
-            let stop = &stop[0];
-            format!("{} {}\n", stop.number, stop.name)
+
+        for scheduled_stop in &route_schedule.scheduled_stops {
+            if !variant_keys.contains(&scheduled_stop.variant.key.as_str()) {
+                variant_keys.push(&scheduled_stop.variant.key);
+            }
         }
+    }
+
+    let directions = join_all(variant_keys.iter().map(|variant_key| {
+        fetch_variant_direction(
+            stop_number,
+            variant_key,
+            config,
+            winnipeg_transit_api_address.clone(),
+            maybe_incoming_message_id,
+            db,
+        )
+    }))
+    .await;
+
+    variant_keys
+        .into_iter()
+        .zip(directions)
+        .filter_map(|(variant_key, direction)| Some((variant_key.to_string(), direction?)))
+        .collect()
+}
+
+// The heading from this stop to the variant’s next one, or from its previous one at the end
+async fn fetch_variant_direction(
+    stop_number: u32,
+    variant_key: &str,
+    config: &Config,
+    winnipeg_transit_api_address: String,
+    maybe_incoming_message_id: Option<Uuid>,
+    db: &PgPool,
+) -> Option<Direction> {
+    let query = format!(
+        "/v4/stops.json?variant={}&usage=short",
+        form_urlencoded::byte_serialize(variant_key.as_bytes()).collect::<String>()
+    );
+
+    let (api_response_status, api_response_text) = fetch_from_odws(
+        query,
+        config,
+        winnipeg_transit_api_address,
+        maybe_incoming_message_id,
+        db,
+    )
+    .await;
+
+    if !api_response_status.is_success() {
+        return None;
+    }
+
+    let path = serde_json::from_str::<VariantStopsResponse>(&api_response_text)
+        .ok()?
+        .stops;
+    let index = path.iter().position(|stop| stop.number == stop_number)?;
+
+    let (from, to) = match path.get(index + 1) {
+        Some(next_stop) => (&path[index], next_stop),
+        None => (path.get(index.checked_sub(1)?)?, &path[index]),
     };
 
-    let mut schedule_lines: Vec<(NaiveDateTime, String)> = Vec::new();
-    let mut route_matched = false;
+    Some(compass_direction(
+        &from.centre.geographic,
+        &to.centre.geographic,
+    ))
+}
 
-    for route_schedule in &parsed_response.stop_schedule.route_schedules {
-        let number_as_string;
-        let route_number = match &route_schedule.route.number {
-            Value::String(s) => s,
-            Value::Number(n) => {
-                number_as_string = n.to_string();
-                &number_as_string
-            }
-            _ => panic!("Unexpected type parsing route number"),
-        };
+fn compass_direction(from: &Coordinates, to: &Coordinates) -> Direction {
+    let from_latitude = from.latitude.to_radians();
+    let to_latitude = to.latitude.to_radians();
+    let longitude_delta = (to.longitude - from.longitude).to_radians();
 
-        if !command.routes.is_empty()
-            && !command
-                .routes
-                .iter()
-                .any(|r| r.eq_ignore_ascii_case(route_number))
-        {
+    let bearing = (longitude_delta.sin() * to_latitude.cos())
+        .atan2(
+            from_latitude.cos() * to_latitude.sin()
+                - from_latitude.sin() * to_latitude.cos() * longitude_delta.cos(),
+        )
+        .to_degrees()
+        .rem_euclid(360.0);
+
+    match bearing {
+        b if b < 45.0 => Direction::North,
+        b if b < 135.0 => Direction::East,
+        b if b < 225.0 => Direction::South,
+        b if b < 315.0 => Direction::West,
+        _ => Direction::North,
+    }
+}
+
+fn append_departures(
+    stop_schedule_response: &StopScheduleResponse,
+    stop: &Stop,
+    command: &TimesCommand,
+    variant_directions: &HashMap<String, Direction>,
+    line_prefix: &str,
+    departures: &mut Vec<Departure>,
+    route_matched: &mut bool,
+) {
+    let routes = &command.routes;
+
+    let stop_direction_matched = command.direction.map_or(true, |direction| {
+        stop.direction
+            .as_ref()
+            .is_some_and(|d| d.to_lowercase().starts_with(direction.name()))
+    });
+
+    let query_time =
+        NaiveDateTime::parse_from_str(&stop_schedule_response.query_time, "%Y-%m-%dT%H:%M:%S")
+            .unwrap();
+
+    for route_schedule in &stop_schedule_response.stop_schedule.route_schedules {
+        let route_number = route_schedule.route.number_string();
+
+        if !route_schedule.route.matches(routes) {
             continue;
         }
 
-        route_matched = true;
+        *route_matched = true;
 
         for scheduled_stop in &route_schedule.scheduled_stops {
+            if let Some(direction) = command.direction {
+                let direction_matched = match variant_directions.get(&scheduled_stop.variant.key) {
+                    Some(variant_direction) => *variant_direction == direction,
+                    // Without the variant’s path the stop’s own direction is the best guess
+                    None => stop_direction_matched,
+                };
+
+                if !direction_matched {
+                    continue;
+                }
+            }
+
+            if let Some(destination) = &command.destination {
+                if !scheduled_stop
+                    .variant
+                    .name
+                    .to_lowercase()
+                    .contains(&destination.to_lowercase())
+                {
+                    continue;
+                }
+            }
+
+            let departure_times = &scheduled_stop.times.departure;
+
+            // Without an estimate the scheduled time is all there is
+            let scheduled_only = departure_times.estimated.is_none();
+
             let time = NaiveDateTime::parse_from_str(
-                &scheduled_stop.times.departure.estimated,
+                departure_times
+                    .estimated
+                    .as_ref()
+                    .unwrap_or(&departure_times.scheduled),
                 "%Y-%m-%dT%H:%M:%S",
             )
             .unwrap();
 
-            let scheduled_time = NaiveDateTime::parse_from_str(
-                &scheduled_stop.times.departure.scheduled,
-                "%Y-%m-%dT%H:%M:%S",
-            )
-            .unwrap();
+            let scheduled_time =
+                NaiveDateTime::parse_from_str(&departure_times.scheduled, "%Y-%m-%dT%H:%M:%S")
+                    .unwrap();
+
+            let label = format!(
+                "{}{} {}",
+                line_prefix, route_number, scheduled_stop.variant.name
+            );
 
-            let mut line = format!("{} {}", route_number, scheduled_stop.variant.name);
+            let mut status = String::new();
 
-            if time.signed_duration_since(scheduled_time).num_minutes() >= DELAY_THRESHOLD {
-                line.push_str(
+            if scheduled_stop.cancelled {
+                status.push_str(" ✕ cancelled");
+            } else if time.signed_duration_since(scheduled_time).num_minutes() >= DELAY_THRESHOLD {
+                status.push_str(
                     format!(
                         " ({}min late)",
                         time.signed_duration_since(scheduled_time).num_minutes()
@@ -99,7 +656,7 @@ pub async fn handle_times_request(
                     .as_str(),
                 );
             } else if time.signed_duration_since(scheduled_time).num_minutes() <= -AHEAD_THRESHOLD {
-                line.push_str(
+                status.push_str(
                     format!(
                         " ({}min ahead)",
                         time.signed_duration_since(scheduled_time)
@@ -110,54 +667,219 @@ pub async fn handle_times_request(
                 );
             }
 
-            schedule_lines.push((time, line));
-        }
-    }
+            let mut amenities = String::new();
 
-    schedule_lines.sort_by(|a, b| a.0.cmp(&b.0));
+            if let Some(bus) = scheduled_stop
+                .bus
+                .as_ref()
+                .filter(|_| !scheduled_stop.cancelled)
+            {
+                if bus.bike_rack {
+                    amenities.push_str(" 🚲");
+                }
 
-    let time_format_string = if number.is_some() && !number.as_ref().unwrap().twelve_hour {
-        "%H:%M"
-    } else {
-        "%-I:%M%p"
-    };
+                if bus.easy_access {
+                    amenities.push_str(" ♿");
+                }
+            }
 
-    let sorted_schedule_lines: Vec<String> = schedule_lines
+            departures.push(Departure {
+                time,
+                scheduled_time,
+                minutes_until: time.signed_duration_since(query_time).num_minutes(),
+                scheduled_only,
+                cancelled: scheduled_stop.cancelled,
+                route: route_number.to_string(),
+                label,
+                status,
+                amenities,
+            });
+        }
+    }
+}
+
+fn render_lines(departures: &[Departure], time_display: &TimeDisplay) -> Vec<String> {
+    departures
         .iter()
-        .map(|(time, line)| {
+        .map(|departure| {
             format!(
-                "{} {}",
-                time.format(time_format_string)
-                    .to_string()
-                    .to_lowercase()
-                    .trim_end_matches('m'),
-                line
+                "{} {}{}",
+                time_display.format(departure),
+                departure.label,
+                departure.note()
             )
         })
+        .collect()
+}
+
+// Groups each route and variant’s times under one label, ordered by their first departure
+fn render_grouped(
+    departures: &[Departure],
+    time_display: &TimeDisplay,
+) -> Vec<(String, Vec<String>)> {
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+
+    for departure in departures {
+        let time = format!("{}{}", time_display.format(departure), departure.note());
+
+        match groups
+            .iter_mut()
+            .find(|(label, _)| *label == departure.label)
+        {
+            Some((_, times)) => times.push(time),
+            None => groups.push((departure.label.clone(), vec![time])),
+        }
+    }
+
+    groups
+}
+
+// Summarises evenly spaced departures of a route and variant as a headway, still listing
+// late, early and cancelled ones. Other departures are grouped as usual.
+fn render_headway(
+    departures: &[Departure],
+    time_display: &TimeDisplay,
+) -> Vec<(String, Vec<String>)> {
+    let mut labels: Vec<&str> = Vec::new();
+
+    for departure in departures {
+        if !labels.contains(&departure.label.as_str()) {
+            labels.push(&departure.label);
+        }
+    }
+
+    labels
+        .into_iter()
+        .flat_map(|label| {
+            let label_departures: Vec<Departure> = departures
+                .iter()
+                .filter(|departure| departure.label == label)
+                .cloned()
+                .collect();
+
+            match headway_range(&label_departures) {
+                Some((shortest, longest)) => {
+                    let every = if shortest == longest {
+                        format!("every {} min", shortest)
+                    } else {
+                        format!("every {}–{} min", shortest, longest)
+                    };
+
+                    // A cancelled last trip can’t be caught, so the service runs until the one before
+                    let last_departure = label_departures
+                        .iter()
+                        .rev()
+                        .find(|departure| !departure.cancelled)
+                        .unwrap();
+
+                    let mut summary = format!(
+                        "{} {} until {}",
+                        label,
+                        every,
+                        time_display.format(last_departure)
+                    );
+
+                    for departure in label_departures.iter().filter(|d| !d.status.is_empty()) {
+                        summary.push_str(&format!(
+                            ", {}{}",
+                            time_display.format(departure),
+                            departure.status
+                        ));
+                    }
+
+                    vec![(summary, Vec::new())]
+                }
+                None => render_grouped(&label_departures, time_display),
+            }
+        })
+        .collect()
+}
+
+// The shortest and longest scheduled gaps when departures are frequent and evenly spaced
+fn headway_range(departures: &[Departure]) -> Option<(i64, i64)> {
+    let scheduled_times: Vec<NaiveDateTime> = departures
+        .iter()
+        .filter(|departure| !departure.cancelled)
+        .map(|departure| departure.scheduled_time)
+        .collect();
+
+    if scheduled_times.len() < MINIMUM_HEADWAY_DEPARTURES {
+        return None;
+    }
+
+    let gaps: Vec<i64> = scheduled_times
+        .windows(2)
+        .map(|pair| pair[1].signed_duration_since(pair[0]).num_minutes())
         .collect();
 
-    for line in sorted_schedule_lines {
-        if response_text.len() + line.len() < MAX_RESPONSE_LENGTH {
-            response_text.push_str(&format!("{}\n", line));
+    let shortest = *gaps.iter().min().unwrap();
+    let longest = *gaps.iter().max().unwrap();
+
+    if longest <= MAXIMUM_HEADWAY && longest - shortest <= MAXIMUM_HEADWAY_VARIATION {
+        Some((shortest, longest))
+    } else {
+        None
+    }
+}
+
+struct TimeDisplay<'a> {
+    clock_format: &'a str,
+    countdown_horizon: Option<i64>,
+}
+
+impl TimeDisplay<'_> {
+    // Departures within the countdown horizon show minutes until, later ones show the clock time.
+    // Times without a real-time estimate are marked with a leading ~.
+    fn format(&self, departure: &Departure) -> String {
+        let time = match self.countdown_horizon {
+            Some(horizon) if departure.minutes_until <= horizon => {
+                if departure.minutes_until <= 0 {
+                    "now".to_string()
+                } else {
+                    format!("{}min", departure.minutes_until)
+                }
+            }
+            _ => departure
+                .time
+                .format(self.clock_format)
+                .to_string()
+                .to_lowercase()
+                .trim_end_matches('m')
+                .to_string(),
+        };
+
+        if departure.scheduled_only {
+            format!("~{}", time)
         } else {
-            break;
+            time
         }
     }
+}
 
-    if !route_matched {
-        response_text.push_str(&format!(
-            "No routes found matching {} at this stop",
-            command.routes.join(" ")
-        ));
-    }
+#[derive(Clone)]
+pub(super) struct Departure {
+    time: NaiveDateTime,
+    scheduled_time: NaiveDateTime,
+    minutes_until: i64,
+    scheduled_only: bool,
+    cancelled: bool,
+    route: String,
+    label: String,
+    status: String,
+    amenities: String,
+}
 
-    Ok(response_text)
+impl Departure {
+    fn note(&self) -> String {
+        format!("{}{}", self.status, self.amenities)
+    }
 }
 
 #[derive(Deserialize)]
 #[serde(rename_all = "kebab-case")]
 struct StopScheduleResponse {
     stop_schedule: StopSchedule,
+    query_time: String,
 }
 
 #[derive(Deserialize)]
@@ -180,6 +902,7 @@ enum StopData {
 pub struct Stop {
     name: String,
     number: u32,
+    direction: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -191,8 +914,11 @@ struct RouteSchedule {
 
 #[derive(Deserialize)]
 struct ScheduledStop {
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    cancelled: bool,
     times: Times,
     variant: Variant,
+    bus: Option<Bus>,
 }
 
 #[derive(Deserialize)]
@@ -202,7 +928,7 @@ struct Times {
 
 #[derive(Deserialize)]
 struct ArrivalDeparture {
-    estimated: String,
+    estimated: Option<String>,
     scheduled: String,
 }
 
@@ -211,7 +937,88 @@ struct Route {
     number: Value,
 }
 
+impl Route {
+    fn number_string(&self) -> String {
+        match &self.number {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            _ => panic!("Unexpected type parsing route number"),
+        }
+    }
+
+    fn matches(&self, routes: &[String]) -> bool {
+        let route_number = self.number_string();
+
+        routes.is_empty() || routes.iter().any(|r| r.eq_ignore_ascii_case(&route_number))
+    }
+}
+
 #[derive(Deserialize)]
 struct Variant {
+    key: String,
     name: String,
 }
+
+#[derive(Deserialize)]
+struct VariantStopsResponse {
+    stops: Vec<VariantStop>,
+}
+
+#[derive(Deserialize)]
+struct VariantStop {
+    number: u32,
+    centre: VariantStopCentre,
+}
+
+#[derive(Deserialize)]
+struct VariantStopCentre {
+    geographic: Coordinates,
+}
+
+#[derive(Deserialize)]
+struct Coordinates {
+    latitude: f64,
+    longitude: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Bus {
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    bike_rack: bool,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    easy_access: bool,
+}
+
+// ODWS sends flags as "true" and "false" strings
+fn deserialize_flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Bool(flag) => Ok(flag),
+        Value::String(flag) => Ok(flag.eq_ignore_ascii_case("true")),
+        _ => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_next_service_day_start() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 7).unwrap();
+        let next_date = NaiveDate::from_ymd_opt(2024, 1, 8).unwrap();
+
+        assert_eq!(
+            next_service_day_start(date.and_hms_opt(12, 16, 40).unwrap()),
+            next_date.and_hms_opt(SERVICE_DAY_START_HOUR, 0, 0).unwrap()
+        );
+        assert_eq!(
+            next_service_day_start(date.and_hms_opt(1, 30, 0).unwrap()),
+            date.and_hms_opt(SERVICE_DAY_START_HOUR, 0, 0).unwrap()
+        );
+    }
+}
@@ -5,13 +5,18 @@ use url::Url;
 #[derive(Clone, Debug)]
 pub struct Config {
     pub admin_number: String,
+    pub async_reply_threshold_ms: u64,
     pub auth: String,
     pub database_url: Url,
+    pub outbound_send_interval_ms: u64,
+    pub response_segment_budget: usize,
     pub root_url: Url,
     pub textabus_number: String,
     pub twilio_account_sid: String,
     pub twilio_api_key_sid: String,
     pub twilio_api_key_secret: String,
+    pub twilio_auth_token: String,
+    pub validate_twilio_signatures: bool,
     pub winnipeg_transit_api_key: String,
 }
 
@@ -28,9 +33,32 @@ impl EnvVarProvider {
                 .get("ADMIN_NUMBER")
                 .expect("Missing admin number")
                 .to_string(),
+            // Twilio abandons webhooks after 15 seconds, slower replies are sent separately
+            async_reply_threshold_ms: args.get("ASYNC_REPLY_THRESHOLD_MS").map_or(
+                10000,
+                |threshold| {
+                    threshold
+                        .parse()
+                        .expect("Unable to parse ASYNC_REPLY_THRESHOLD_MS as a number")
+                },
+            ),
             auth: args.get("AUTH").expect("Missing auth").to_string(),
             database_url: Url::parse(args.get("DATABASE_URL").expect("Missing DATABASE_URL"))
                 .expect("Unable to parse DATABASE_URL as a URL"),
+            // Twilio queues messages beyond about one per second on a long code
+            outbound_send_interval_ms: args.get("OUTBOUND_SEND_INTERVAL_MS").map_or(
+                1000,
+                |interval| {
+                    interval
+                        .parse()
+                        .expect("Unable to parse OUTBOUND_SEND_INTERVAL_MS as a number")
+                },
+            ),
+            response_segment_budget: args.get("RESPONSE_SEGMENT_BUDGET").map_or(1, |budget| {
+                budget
+                    .parse()
+                    .expect("Unable to parse RESPONSE_SEGMENT_BUDGET as a number")
+            }),
             root_url: Url::parse(args.get("ROOT_URL").expect("Missing ROOT_URL"))
                 .expect("Unable to parse ROOT_URL as a URL"),
             textabus_number: args
@@ -49,6 +77,14 @@ impl EnvVarProvider {
                 .get("TWILIO_API_KEY_SECRET")
                 .expect("Missing Twilio API key secret")
                 .to_string(),
+            twilio_auth_token: args
+                .get("TWILIO_AUTH_TOKEN")
+                .expect("Missing Twilio auth token")
+                .to_string(),
+            // Only for local development, where requests don’t come from Twilio
+            validate_twilio_signatures: args
+                .get("VALIDATE_TWILIO_SIGNATURES")
+                .map_or(true, |value| value != "false"),
             winnipeg_transit_api_key: args
                 .get("WINNIPEG_TRANSIT_API_KEY")
                 .expect("Missing WINNIPEG_TRANSIT_API_KEY")
@@ -70,3 +106,46 @@ impl Default for EnvVarProvider {
         Self::new(HashMap::new())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn required_args() -> HashMap<String, String> {
+        [
+            ("ADMIN_NUMBER", "+1311"),
+            ("AUTH", "username:password"),
+            ("DATABASE_URL", "postgres://localhost/textabus"),
+            ("ROOT_URL", "http://example.com"),
+            ("TEXTABUS_NUMBER", "+12042877433"),
+            ("TWILIO_ACCOUNT_SID", "FAKE"),
+            ("TWILIO_API_KEY_SID", "FAKE"),
+            ("TWILIO_API_KEY_SECRET", "FAKE"),
+            ("TWILIO_AUTH_TOKEN", "FAKE"),
+            ("WINNIPEG_TRANSIT_API_KEY", "FAKE"),
+        ]
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn test_validate_twilio_signatures_switch() {
+        let mut args = required_args();
+        assert!(
+            EnvVarProvider::new(args.clone())
+                .get_config()
+                .validate_twilio_signatures
+        );
+
+        args.insert(
+            "VALIDATE_TWILIO_SIGNATURES".to_string(),
+            "false".to_string(),
+        );
+        assert!(
+            !EnvVarProvider::new(args)
+                .get_config()
+                .validate_twilio_signatures
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Number;
+use sqlx::{types::Json, PgPool};
+
+const CONVERSATION_STATE_MINUTES: i64 = 15;
+
+// Short-lived context from a previous reply, such as a numbered list to pick from
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ConversationState {
+    Stops {
+        stop_numbers: Vec<u64>,
+    },
+    Locations {
+        routes: Vec<String>,
+        locations: Vec<LocationChoice>,
+        // Whether the list came from near, which answers with times rather than stops
+        #[serde(default)]
+        near: bool,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LocationChoice {
+    pub name: String,
+    pub latitude: Number,
+    pub longitude: Number,
+}
+
+pub async fn get_conversation_state(
+    db: &PgPool,
+    number: &str,
+) -> Result<Option<ConversationState>, sqlx::Error> {
+    let state = sqlx::query_scalar::<_, Option<Json<ConversationState>>>(
+        r#"
+        SELECT state FROM conversation_states
+        WHERE number = $1 AND expires_at > $2
+        "#,
+    )
+    .bind(number)
+    .bind(Utc::now().naive_utc())
+    .fetch_optional(db)
+    .await?;
+
+    Ok(state.flatten().map(|Json(state)| state))
+}
+
+pub async fn set_conversation_state(
+    db: &PgPool,
+    number: &str,
+    state: ConversationState,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO conversation_states (number, state, expires_at, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (number) DO UPDATE
+        SET state = EXCLUDED.state, expires_at = EXCLUDED.expires_at, updated_at = EXCLUDED.updated_at
+        "#,
+    )
+    .bind(number)
+    .bind(Json(state))
+    .bind((Utc::now() + Duration::minutes(CONVERSATION_STATE_MINUTES)).naive_utc())
+    .bind(Utc::now().naive_utc())
+    .bind(Utc::now().naive_utc())
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+// Entries left over from a long reply, they expire on their own so a late `more` can’t send stale
+// departures however recently a list was sent
+pub async fn get_overflow(db: &PgPool, number: &str) -> Result<Option<Vec<String>>, sqlx::Error> {
+    let overflow = sqlx::query_scalar::<_, Option<Vec<String>>>(
+        r#"
+        SELECT overflow FROM conversation_states
+        WHERE number = $1 AND overflow_expires_at > $2
+        "#,
+    )
+    .bind(number)
+    .bind(Utc::now().naive_utc())
+    .fetch_optional(db)
+    .await?;
+
+    Ok(overflow.flatten())
+}
+
+pub async fn set_overflow(
+    db: &PgPool,
+    number: &str,
+    overflow: Vec<String>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO conversation_states (number, overflow, overflow_expires_at, expires_at, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $4, $4)
+        ON CONFLICT (number) DO UPDATE
+        SET overflow = EXCLUDED.overflow, overflow_expires_at = EXCLUDED.overflow_expires_at, updated_at = EXCLUDED.updated_at
+        "#,
+    )
+    .bind(number)
+    .bind(overflow)
+    .bind((Utc::now() + Duration::minutes(CONVERSATION_STATE_MINUTES)).naive_utc())
+    .bind(Utc::now().naive_utc())
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn clear_overflow(db: &PgPool, number: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE conversation_states
+        SET overflow = NULL, overflow_expires_at = NULL, updated_at = $2
+        WHERE number = $1
+        "#,
+    )
+    .bind(number)
+    .bind(Utc::now().naive_utc())
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
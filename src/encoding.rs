@@ -0,0 +1,181 @@
+// SMS segment counting, see https://www.twilio.com/docs/glossary/what-sms-character-limit
+
+use crate::models::Number;
+
+const GSM7_BASIC: &str = "@£$¥èéùìòÇ\nØø\rÅåΔ_ΦΓΛΩΠΨΣΘΞÆæßÉ !\"#¤%&'()*+,-./0123456789:;<=>?¡ABCDEFGHIJKLMNOPQRSTUVWXYZÄÖÑÜ§¿abcdefghijklmnopqrstuvwxyzäöñüà";
+
+// These take an escape character and so count twice
+const GSM7_EXTENSION: &str = "\u{c}^{}\\[~]|€";
+
+const GSM7_SINGLE_SEGMENT: usize = 160;
+const GSM7_CONCATENATED_SEGMENT: usize = 153;
+const UCS2_SINGLE_SEGMENT: usize = 70;
+const UCS2_CONCATENATED_SEGMENT: usize = 67;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    Gsm7,
+    Ucs2,
+}
+
+pub fn is_gsm7(character: char) -> bool {
+    GSM7_BASIC.contains(character) || GSM7_EXTENSION.contains(character)
+}
+
+// A single character outside GSM-7 makes the whole message UCS-2
+pub fn encoding(text: &str) -> Encoding {
+    if text.chars().all(is_gsm7) {
+        Encoding::Gsm7
+    } else {
+        Encoding::Ucs2
+    }
+}
+
+pub fn segment_count(text: &str) -> usize {
+    let (units, single_segment, concatenated_segment) = match encoding(text) {
+        Encoding::Gsm7 => (
+            text.chars()
+                .map(|character| {
+                    if GSM7_EXTENSION.contains(character) {
+                        2
+                    } else {
+                        1
+                    }
+                })
+                .sum(),
+            GSM7_SINGLE_SEGMENT,
+            GSM7_CONCATENATED_SEGMENT,
+        ),
+        // Characters beyond the Basic Multilingual Plane, like emoji, take two units
+        Encoding::Ucs2 => (
+            text.encode_utf16().count(),
+            UCS2_SINGLE_SEGMENT,
+            UCS2_CONCATENATED_SEGMENT,
+        ),
+    };
+
+    if units <= single_segment {
+        1
+    } else {
+        units.div_ceil(concatenated_segment)
+    }
+}
+
+// Replacements that keep the meaning, characters without one are left as they are
+fn gsm7_equivalent(character: char) -> Option<&'static str> {
+    Some(match character {
+        '‘' | '’' | '‚' | '′' | '`' | '´' => "'",
+        '“' | '”' | '„' | '″' => "\"",
+        '‐' | '‑' | '‒' | '–' | '—' | '−' => "-",
+        '…' => "...",
+        '→' => "->",
+        '←' => "<-",
+        '•' | '·' => "*",
+        '×' => "x",
+        // Departure markers become words, the cancelled marker is already followed by one
+        '✕' => "",
+        '🚲' => "bike",
+        '♿' => "access",
+        '\u{a0}' | '\u{2009}' | '\u{202f}' => " ",
+        'á' | 'â' | 'ã' | 'ā' => "a",
+        'Á' | 'À' | 'Â' | 'Ã' | 'Ā' => "A",
+        'ç' => "c",
+        'ê' | 'ë' | 'ē' => "e",
+        'È' | 'Ê' | 'Ë' | 'Ē' => "E",
+        'í' | 'î' | 'ï' | 'ī' => "i",
+        'Í' | 'Ì' | 'Î' | 'Ï' | 'Ī' => "I",
+        'ó' | 'ô' | 'õ' | 'ō' => "o",
+        'Ó' | 'Ò' | 'Ô' | 'Õ' | 'Ō' => "O",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'ú' | 'û' | 'ū' => "u",
+        'Ú' | 'Ù' | 'Û' | 'Ū' => "U",
+        'ý' | 'ÿ' => "y",
+        'Ý' | 'Ÿ' => "Y",
+        _ => return None,
+    })
+}
+
+pub fn transliterate(text: &str) -> String {
+    let mut transliterated = String::with_capacity(text.len());
+
+    let mut characters = text.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        match (is_gsm7(character), gsm7_equivalent(character)) {
+            // A dropped character takes its following space with it
+            (false, Some("")) => {
+                characters.next_if_eq(&' ');
+            }
+            (false, Some(equivalent)) => transliterated.push_str(equivalent),
+            _ => transliterated.push(character),
+        }
+    }
+
+    transliterated
+}
+
+// Text is transliterated unless the number has asked to keep accents
+pub fn prepare_outbound(text: &str, number: Option<&Number>) -> String {
+    if number.is_some_and(|number| number.accents) {
+        text.to_string()
+    } else {
+        transliterate(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(encoding("10619 Osborne@Jubilee"), Encoding::Gsm7);
+        assert_eq!(encoding("café à 5€ {}"), Encoding::Gsm7);
+        assert_eq!(encoding("we don’t recognise you"), Encoding::Ucs2);
+        assert_eq!(encoding("Osborne → Jubilee"), Encoding::Ucs2);
+        assert_eq!(encoding("12:39p 🚲 ♿"), Encoding::Ucs2);
+    }
+
+    #[test]
+    fn test_transliterate() {
+        assert_eq!(
+            transliterate("we don’t recognise you"),
+            "we don't recognise you"
+        );
+        assert_eq!(
+            transliterate("BLUE every 11–12 min"),
+            "BLUE every 11-12 min"
+        );
+        assert_eq!(transliterate("[route]…"), "[route]...");
+        assert_eq!(transliterate("Crêperie Côté"), "Creperie Coté");
+        assert_eq!(transliterate("Église St-Émile"), "Église St-Émile");
+        assert_eq!(transliterate("12:39p 🚲 ♿"), "12:39p bike access");
+        assert_eq!(transliterate("12:39p ✕ cancelled"), "12:39p cancelled");
+        assert_eq!(
+            encoding(&transliterate("“Osborne” → Jubilee")),
+            Encoding::Gsm7
+        );
+    }
+
+    #[test]
+    fn test_gsm7_segment_count() {
+        assert_eq!(segment_count(""), 1);
+        assert_eq!(segment_count(&"a".repeat(160)), 1);
+        assert_eq!(segment_count(&"a".repeat(161)), 2);
+        assert_eq!(segment_count(&"a".repeat(306)), 2);
+        assert_eq!(segment_count(&"a".repeat(307)), 3);
+        assert_eq!(segment_count(&"€".repeat(80)), 1);
+        assert_eq!(segment_count(&"€".repeat(81)), 2);
+    }
+
+    #[test]
+    fn test_ucs2_segment_count() {
+        assert_eq!(segment_count(&format!("’{}", "a".repeat(69))), 1);
+        assert_eq!(segment_count(&format!("’{}", "a".repeat(70))), 2);
+        assert_eq!(segment_count(&format!("’{}", "a".repeat(133))), 2);
+        assert_eq!(segment_count(&format!("’{}", "a".repeat(134))), 3);
+        assert_eq!(segment_count(&"🚲".repeat(35)), 1);
+        assert_eq!(segment_count(&"🚲".repeat(36)), 2);
+    }
+}
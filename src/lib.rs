@@ -1,13 +1,19 @@
+pub mod aliases;
 pub mod auth;
 pub mod commands;
 pub mod config;
+pub mod conversation;
+pub mod encoding;
 pub mod models;
 pub mod odws;
 pub mod render_xml;
 pub mod routes;
+pub mod signature;
+pub mod sms;
 
 use crate::config::{Config, ConfigProvider, EnvVarProvider};
 use crate::routes::*;
+use crate::sms::{OutboundQueue, SmsSender, TwilioSmsSender};
 
 use axum::{
     routing::{get, post},
@@ -16,7 +22,7 @@ use axum::{
 use axum_template::engine::Engine;
 use handlebars::{DirectorySourceOptions, Handlebars};
 use sqlx::postgres::PgPool;
-use std::env;
+use std::{env, sync::Arc, time::Duration};
 use tower_http::services::ServeDir;
 
 type AppEngine = Engine<Handlebars<'static>>;
@@ -26,12 +32,14 @@ pub struct AppState {
     config: Config,
     db: PgPool,
     engine: AppEngine,
-    twilio_address: String,
+    outbound_queue: OutboundQueue,
+    sms_sender: Arc<dyn SmsSender>,
     winnipeg_transit_api_address: String,
 }
 
 pub struct InjectableServices {
     pub db: PgPool,
+    pub sms_sender: Option<Arc<dyn SmsSender>>,
     pub twilio_address: Option<String>,
     pub winnipeg_transit_api_address: Option<String>,
 }
@@ -51,15 +59,35 @@ pub async fn app(services: InjectableServices) -> Router {
     let env_config_provider = EnvVarProvider::new(env::vars().collect());
     let config = env_config_provider.get_config();
 
+    let sms_sender: Arc<dyn SmsSender> = services.sms_sender.unwrap_or_else(|| {
+        Arc::new(TwilioSmsSender::new(
+            services.twilio_address.unwrap(),
+            config,
+        ))
+    });
+
+    let outbound_queue = OutboundQueue::start(
+        sms_sender.clone(),
+        services.db.clone(),
+        Duration::from_millis(config.outbound_send_interval_ms),
+    );
+
     Router::new()
         .nest_service("/assets", ServeDir::new("assets"))
         .route("/", get(get_root))
         .route("/about", get(get_about))
         .route("/changelog", get(get_changelog))
-        .route("/twilio", get(get_twilio))
+        .route("/twilio", get(get_twilio).post(post_twilio))
+        .route("/twilio/status", post(post_twilio_status))
         .route("/raw", get(get_raw))
         .route("/admin/messages", get(get_messages))
         .route("/admin/numbers", get(get_numbers))
+        .route(
+            "/admin/announcements",
+            get(get_announcements).post(post_announcements),
+        )
+        .route("/admin/aliases", get(get_aliases).post(post_aliases))
+        .route("/admin/aliases/:id", get(get_alias).post(post_alias))
         .route("/admin/numbers/:number/approve", post(post_approve_number))
         .route(
             "/admin/numbers/:number/unapprove",
@@ -69,7 +97,8 @@ pub async fn app(services: InjectableServices) -> Router {
             config: config.clone(),
             db: services.db,
             engine: Engine::from(hbs),
-            twilio_address: services.twilio_address.unwrap(),
+            outbound_queue,
+            sms_sender,
             winnipeg_transit_api_address: services.winnipeg_transit_api_address.unwrap(),
         })
 }
@@ -38,6 +38,7 @@ fn main() {
                 listener,
                 app(InjectableServices {
                     db,
+                    sms_sender: None,
                     twilio_address: Some("https://api.twilio.com".to_string()),
                     winnipeg_transit_api_address: Some(
                         "https://api.winnipegtransit.com".to_string(),
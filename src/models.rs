@@ -20,6 +20,13 @@ pub struct Message {
     pub destination: String,
     pub body: String,
     pub initial_message_id: Option<Uuid>,
+    pub num_media: Option<i32>,
+    pub from_city: Option<String>,
+    pub sms_status: Option<String>,
+    pub send_error: Option<String>,
+    pub delivery_status: Option<String>,
+    pub delivery_error_code: Option<i32>,
+    pub sent_via_api: bool,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
@@ -31,4 +38,28 @@ pub struct Number {
     pub approved: bool,
     pub admin: bool,
     pub twelve_hour: bool,
+    pub times_format: TimesFormat,
+    pub countdown_horizon: Option<i32>,
+    pub accents: bool,
+    pub opted_out: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "times_format", rename_all = "lowercase")]
+pub enum TimesFormat {
+    Lines,
+    Grouped,
+    Headway,
+}
+
+#[derive(Clone, Debug, sqlx::FromRow, Serialize)]
+pub struct Alias {
+    pub id: Uuid,
+    pub name: String,
+    pub query: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
 }
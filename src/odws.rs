@@ -1,10 +1,16 @@
-use chrono::Utc;
+use chrono::{NaiveDateTime, Utc};
+use chrono_tz::America::Winnipeg;
 use reqwest::Client;
 use sqlx::{types::Uuid, PgPool};
 use url::Url;
 
 use crate::config::Config;
 
+// ODWS times are Winnipeg wall clock times, whatever the server’s time zone
+pub fn winnipeg_now() -> NaiveDateTime {
+    Utc::now().with_timezone(&Winnipeg).naive_local()
+}
+
 pub async fn fetch_from_odws(
     path: String,
     config: &Config,
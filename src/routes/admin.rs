@@ -1,14 +1,21 @@
-use crate::{auth::User, models::Number, routes::HELP_MESSAGE, AppState};
+use crate::{
+    auth::User,
+    encoding::{segment_count, transliterate},
+    models::{Alias, Number},
+    routes::HELP_MESSAGE,
+    sms::{send_sms, OutboundSms},
+    AppState,
+};
 
 use axum::{
     extract::{Path, State},
-    response::{IntoResponse, Response},
+    response::{IntoResponse, Redirect, Response},
+    Form,
 };
 use axum_template::RenderHtml;
-use base64::{engine::general_purpose, Engine as _};
 use chrono::{NaiveDateTime, Utc};
 use http::StatusCode;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::types::uuid::Uuid;
 use std::collections::HashMap;
 
@@ -92,22 +99,6 @@ pub async fn post_approve_number(
     State(state): State<AppState>,
     Path(id): Path<String>,
 ) -> Response {
-    let config = state.config;
-
-    let account_sid = config.twilio_account_sid.to_string();
-    let api_sid = config.twilio_api_key_sid.to_string();
-    let api_secret = config.twilio_api_key_secret.to_string();
-
-    let client = reqwest::Client::new();
-
-    let basic_auth = format!("{}:{}", api_sid, api_secret);
-    let auth_header_value = format!(
-        "Basic {}",
-        general_purpose::STANDARD_NO_PAD.encode(basic_auth)
-    );
-
-    let approved_notification_body = get_composed_approval_message();
-
     sqlx::query(
         r#"
             UPDATE numbers
@@ -120,43 +111,16 @@ pub async fn post_approve_number(
     .await
     .expect("Failed to update number");
 
-    let create_message_body = serde_urlencoded::to_string([
-        ("Body", approved_notification_body.clone()),
-        ("To", id.clone()),
-        ("From", config.textabus_number.clone()),
-    ])
-    .expect("Could not encode meeting message creation body");
-
-    client
-        .post(format!(
-            "{}/2010-04-01/Accounts/{}/Messages.json",
-            state.twilio_address, account_sid
-        ))
-        .header("Authorization", auth_header_value.clone())
-        .header("Content-Type", "application/x-www-form-urlencoded")
-        .body(create_message_body.clone())
-        .send()
-        .await
-        .ok();
-
-    let admin_message_insertion_result = sqlx::query(
-        r#"
-        INSERT INTO messages (id, origin, destination, body, created_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, $6)
-        "#,
+    send_sms(
+        state.sms_sender.as_ref(),
+        &state.db,
+        &state.config.textabus_number,
+        &id,
+        &get_composed_approval_message(),
+        None,
     )
-    .bind(Uuid::new_v4())
-    .bind(config.textabus_number)
-    .bind(id)
-    .bind(approved_notification_body)
-    .bind(Utc::now().naive_utc())
-    .bind(Utc::now().naive_utc())
-    .execute(&state.db)
-    .await;
-
-    if let Err(e) = admin_message_insertion_result {
-        log::error!("Failed to insert approval message: {}", e);
-    }
+    .await
+    .ok();
 
     StatusCode::NO_CONTENT.into_response()
 }
@@ -181,6 +145,284 @@ pub async fn post_unapprove_number(
     StatusCode::NO_CONTENT.into_response()
 }
 
+pub async fn get_aliases(State(state): State<AppState>, _user: User) -> impl IntoResponse {
+    let aliases = sqlx::query_as::<_, Alias>(
+        r#"
+            SELECT *
+            FROM aliases
+            ORDER BY LOWER(name)
+        "#,
+    )
+    .fetch_all(&state.db)
+    .await
+    .expect("Failed to fetch aliases");
+
+    RenderHtml("admin/aliases", state.engine, AliasesTemplate { aliases })
+}
+
+pub async fn post_aliases(
+    State(state): State<AppState>,
+    _user: User,
+    Form(form): Form<AliasForm>,
+) -> Response {
+    let (name, query, latitude, longitude) = match form.validate() {
+        Ok(fields) => fields,
+        Err(message) => return (StatusCode::UNPROCESSABLE_ENTITY, message).into_response(),
+    };
+
+    let insertion_result = sqlx::query(
+        r#"
+            INSERT INTO aliases (id, name, query, latitude, longitude, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(name)
+    .bind(query)
+    .bind(latitude)
+    .bind(longitude)
+    .bind(Utc::now().naive_utc())
+    .bind(Utc::now().naive_utc())
+    .execute(&state.db)
+    .await;
+
+    if let Err(e) = insertion_result {
+        log::error!("Failed to insert alias: {}", e);
+        return alias_error_response(e);
+    }
+
+    Redirect::to("/admin/aliases").into_response()
+}
+
+pub async fn get_alias(
+    State(state): State<AppState>,
+    _user: User,
+    Path(id): Path<Uuid>,
+) -> Response {
+    let alias = sqlx::query_as::<_, Alias>(
+        r#"
+            SELECT *
+            FROM aliases
+            WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await
+    .expect("Failed to fetch alias");
+
+    match alias {
+        Some(alias) => {
+            RenderHtml("admin/alias", state.engine, AliasTemplate { alias }).into_response()
+        }
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+pub async fn post_alias(
+    State(state): State<AppState>,
+    _user: User,
+    Path(id): Path<Uuid>,
+    Form(form): Form<AliasForm>,
+) -> Response {
+    let (name, query, latitude, longitude) = match form.validate() {
+        Ok(fields) => fields,
+        Err(message) => return (StatusCode::UNPROCESSABLE_ENTITY, message).into_response(),
+    };
+
+    let update_result = sqlx::query(
+        r#"
+            UPDATE aliases
+            SET name = $2, query = $3, latitude = $4, longitude = $5, updated_at = $6
+            WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(name)
+    .bind(query)
+    .bind(latitude)
+    .bind(longitude)
+    .bind(Utc::now().naive_utc())
+    .execute(&state.db)
+    .await;
+
+    match update_result {
+        Ok(result) if result.rows_affected() == 0 => StatusCode::NOT_FOUND.into_response(),
+        Ok(_) => Redirect::to("/admin/aliases").into_response(),
+        Err(e) => {
+            log::error!("Failed to update alias: {}", e);
+            alias_error_response(e)
+        }
+    }
+}
+
+// Only a clash with the unique name index is the admin’s to fix
+fn alias_error_response(error: sqlx::Error) -> Response {
+    let is_duplicate_name = error
+        .as_database_error()
+        .is_some_and(|database_error| database_error.is_unique_violation());
+
+    if is_duplicate_name {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Alias name already exists",
+        )
+            .into_response()
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AliasForm {
+    name: String,
+    #[serde(default)]
+    query: String,
+    #[serde(default)]
+    latitude: String,
+    #[serde(default)]
+    longitude: String,
+}
+
+type AliasFields = (String, Option<String>, Option<f64>, Option<f64>);
+
+impl AliasForm {
+    // An alias needs either a query to search for instead or both coordinates
+    fn validate(self) -> Result<AliasFields, &'static str> {
+        let name = self.name.trim().to_string();
+
+        if name.is_empty() {
+            return Err("Alias name is required");
+        }
+
+        let query = Some(self.query.trim().to_string()).filter(|query| !query.is_empty());
+
+        let latitude = parse_coordinate(&self.latitude, 90.0)?;
+        let longitude = parse_coordinate(&self.longitude, 180.0)?;
+
+        match (&query, latitude, longitude) {
+            (_, Some(_), Some(_)) | (Some(_), None, None) => Ok((name, query, latitude, longitude)),
+            _ => Err("Alias needs a query or both latitude and longitude"),
+        }
+    }
+}
+
+fn parse_coordinate(value: &str, limit: f64) -> Result<Option<f64>, &'static str> {
+    let value = value.trim();
+
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    match value.parse::<f64>() {
+        Ok(coordinate) if coordinate.abs() <= limit => Ok(Some(coordinate)),
+        _ => Err("Alias coordinates are invalid"),
+    }
+}
+
+pub async fn get_announcements(State(state): State<AppState>, _user: User) -> impl IntoResponse {
+    RenderHtml(
+        "admin/announcements",
+        state.engine,
+        AnnouncementsTemplate {
+            body: String::new(),
+            preview: None,
+        },
+    )
+}
+
+pub async fn post_announcements(
+    State(state): State<AppState>,
+    _user: User,
+    Form(form): Form<AnnouncementForm>,
+) -> Response {
+    // Browsers submit textarea line breaks as CRLF, which would waste a character each
+    let body = form.body.replace("\r\n", "\n").trim().to_string();
+
+    if body.is_empty() {
+        return (StatusCode::UNPROCESSABLE_ENTITY, "Announcement is required").into_response();
+    }
+
+    let recipients = sqlx::query_scalar::<_, String>(
+        r#"
+            SELECT number
+            FROM numbers
+            WHERE approved = TRUE AND opted_out = FALSE
+            ORDER BY created_at
+        "#,
+    )
+    .fetch_all(&state.db)
+    .await
+    .expect("Failed to fetch recipients");
+
+    if form.action != "send" {
+        let text = transliterate(&body);
+        let segments = segment_count(&text);
+        let accents_segments = Some(segment_count(&body)).filter(|count| *count != segments);
+
+        return RenderHtml(
+            "admin/announcements",
+            state.engine,
+            AnnouncementsTemplate {
+                body,
+                preview: Some(AnnouncementPreview {
+                    text,
+                    segments,
+                    accents_segments,
+                    recipients: recipients.len(),
+                }),
+            },
+        )
+        .into_response();
+    }
+
+    for recipient in recipients {
+        let enqueue_result = state.outbound_queue.enqueue(OutboundSms {
+            from: state.config.textabus_number.clone(),
+            to: recipient,
+            body: body.clone(),
+        });
+
+        if let Err(e) = enqueue_result {
+            log::error!("Failed to queue announcement: {}", e);
+        }
+    }
+
+    Redirect::to("/admin/messages").into_response()
+}
+
+#[derive(Deserialize)]
+pub struct AnnouncementForm {
+    body: String,
+    #[serde(default)]
+    action: String,
+}
+
+#[derive(Serialize)]
+struct AnnouncementsTemplate {
+    body: String,
+    preview: Option<AnnouncementPreview>,
+}
+
+// Recipients who keep accents get the text as written, everyone else the plain version
+#[derive(Serialize)]
+struct AnnouncementPreview {
+    text: String,
+    segments: usize,
+    accents_segments: Option<usize>,
+    recipients: usize,
+}
+
+#[derive(Serialize)]
+struct AliasesTemplate {
+    aliases: Vec<Alias>,
+}
+
+#[derive(Serialize)]
+struct AliasTemplate {
+    alias: Alias,
+}
+
 #[derive(Serialize)]
 struct MessagesTemplate {
     exchanges: Vec<Exchange>,
@@ -201,6 +443,12 @@ pub struct ExtendedMessage {
     pub destination: String,
     pub body: String,
     pub initial_message_id: Option<Uuid>,
+    pub num_media: Option<i32>,
+    pub from_city: Option<String>,
+    pub sms_status: Option<String>,
+    pub send_error: Option<String>,
+    pub delivery_status: Option<String>,
+    pub delivery_error_code: Option<i32>,
     pub created_at: NaiveDateTime,
     pub formatted_created_at: String,
     pub updated_at: NaiveDateTime,
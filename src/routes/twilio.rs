@@ -1,24 +1,31 @@
 use crate::{
     commands::{
-        handle_settings_clock_request, handle_stops_request, handle_times_request, parse_command,
-        Command,
+        clear_pending_pages, handle_choice_request, handle_more_request, handle_near_request,
+        handle_opt_out_keyword, handle_service_request, handle_settings_accents_request,
+        handle_settings_clock_request, handle_settings_countdown_request,
+        handle_settings_format_request, handle_stops_request, handle_times_request, paginate,
+        parse_command, parse_opt_out_keyword, Command, OptOutKeyword,
     },
-    models::Number,
-    render_xml::RenderXml,
+    encoding::prepare_outbound,
+    models::{Message, Number},
+    render_xml::{RenderXml, Xml},
+    signature::is_valid_twilio_signature,
+    sms::send_sms,
     AppState,
 };
 
 use axum::{
     extract::{ConnectInfo, Query, State},
-    response::IntoResponse,
+    http::{HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Response},
 };
-use base64::{engine::general_purpose, Engine as _};
 use chrono::Utc;
 use indoc::indoc;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, NoneAsEmptyString};
 use sqlx::types::Uuid;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Duration};
+use tokio::{task::JoinHandle, time::timeout};
 
 pub const HELP_MESSAGE: &str = indoc!(
     r#"
@@ -27,26 +34,82 @@ pub const HELP_MESSAGE: &str = indoc!(
     bus times:
     [stop number]
     [stop number] [route] [route]…
+    [stop number] [stop number]… [route]…
+    [stop number] [route] to [destination]
+    [stop number]… [north/south/east/west]
     times [stop number]
+    last [stop number] [route]…
+    first [stop number] [route]…
 
     find stops:
-    stops [location: address, intersection, landmark]
+    stops [location: address, intersection, landmark, map link]
+    stops [location] on [route]…
+    then reply with a listed number for its times
+
+    route times at the closest stops:
+    [route]… near [location]
 
     toggle 12h/24h clock in times response:
     settings clock
+
+    one departure per line, grouped by route or summarised when frequent:
+    settings format [lines/grouped/headway]
+
+    minutes until departure for soon buses:
+    settings countdown [minutes/off]
+
+    keep accents and symbols instead of plain characters:
+    settings accents [on/off]
+
+    next page of a long response:
+    more
     "#
 );
 
 #[axum_macros::debug_handler]
 pub async fn get_twilio(
     State(state): State<AppState>,
-    params: Query<TwilioParams>,
-) -> impl IntoResponse {
+    uri: Uri,
+    headers: HeaderMap,
+    Query(params): Query<TwilioParams>,
+) -> Response {
+    if !has_valid_signature(&state, &uri, &headers, &[]) {
+        return (StatusCode::FORBIDDEN, "invalid signature").into_response();
+    }
+
+    respond_to_twilio(state, params).await
+}
+
+// Twilio’s default webhook method, the signature covers the form parameters too
+#[axum_macros::debug_handler]
+pub async fn post_twilio(
+    State(state): State<AppState>,
+    uri: Uri,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let (Ok(form_params), Ok(params)) = (
+        serde_urlencoded::from_str::<Vec<(String, String)>>(&body),
+        serde_urlencoded::from_str::<TwilioParams>(&body),
+    ) else {
+        return (StatusCode::UNPROCESSABLE_ENTITY, "invalid parameters").into_response();
+    };
+
+    if !has_valid_signature(&state, &uri, &headers, &form_params) {
+        return (StatusCode::FORBIDDEN, "invalid signature").into_response();
+    }
+
+    respond_to_twilio(state, params).await
+}
+
+async fn respond_to_twilio(state: AppState, params: TwilioParams) -> Response {
     let incoming_message_id = Uuid::new_v4();
     let incoming_message_insertion_result = sqlx::query(
         r#"
-        INSERT INTO messages (id, message_sid, origin, destination, body, created_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        INSERT INTO messages (id, message_sid, origin, destination, body, num_media, from_city, sms_status, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        ON CONFLICT (message_sid) WHERE initial_message_id IS NULL AND message_sid <> 'repl'
+        DO NOTHING
         "#,
     )
     .bind(incoming_message_id)
@@ -54,6 +117,9 @@ pub async fn get_twilio(
     .bind(params.from.clone())
     .bind(params.to.clone())
     .bind(params.body.clone())
+    .bind(params.num_media)
+    .bind(params.from_city.clone())
+    .bind(params.sms_status.clone())
     .bind(Utc::now().naive_utc())
     .bind(Utc::now().naive_utc())
     .execute(&state.db)
@@ -61,9 +127,15 @@ pub async fn get_twilio(
 
     let mut maybe_incoming_message_id = Some(incoming_message_id);
 
-    if let Err(e) = incoming_message_insertion_result {
-        maybe_incoming_message_id = None;
-        log::error!("Failed to insert incoming message: {}", e);
+    match incoming_message_insertion_result {
+        Ok(result) if result.rows_affected() == 0 => {
+            return respond_to_retry(state, params).await;
+        }
+        Ok(_) => {}
+        Err(e) => {
+            maybe_incoming_message_id = None;
+            log::error!("Failed to insert incoming message: {}", e);
+        }
     }
 
     let mut response_text = "textabus".to_string();
@@ -78,77 +150,83 @@ pub async fn get_twilio(
     .fetch_one(&state.db)
     .await;
 
-    if maybe_number.is_ok() {
-        let number = maybe_number.unwrap();
-        if number.approved {
+    // Start only resubscribes, from any other number it’s handled like any other text
+    let opt_out_keyword = params
+        .body
+        .as_deref()
+        .and_then(parse_opt_out_keyword)
+        .filter(|keyword| {
+            *keyword == OptOutKeyword::Stop
+                || maybe_number.as_ref().is_ok_and(|number| number.opted_out)
+        });
+
+    if let Some(keyword) = opt_out_keyword {
+        match handle_opt_out_keyword(keyword, &state.db, &params.from).await {
+            Ok(opt_out_text) => response_text = opt_out_text,
+            Err(e) => {
+                log::error!("Failed to handle opt-out keyword: {}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, "internal error").into_response();
+            }
+        }
+    } else if maybe_number.is_ok() {
+        let number = maybe_number.as_ref().unwrap();
+        if number.opted_out {
+            // Nothing is sent to an opted-out number until it texts start
+            return Xml("<Response></Response>").into_response();
+        } else if number.approved {
             if params.body.is_some() {
+                let processing_state = state.clone();
+                let body = params.body.clone();
                 let cloned_number = number.clone();
-                response_text = process_command(
-                    params.body.clone(),
-                    &state,
-                    &Some(cloned_number),
-                    maybe_incoming_message_id,
-                )
-                .await;
+
+                let mut processing = tokio::spawn(async move {
+                    process_command(
+                        body,
+                        &processing_state,
+                        &Some(cloned_number),
+                        maybe_incoming_message_id,
+                    )
+                    .await
+                });
+
+                let threshold = Duration::from_millis(state.config.async_reply_threshold_ms);
+
+                match timeout(threshold, &mut processing).await {
+                    Ok(Ok(processed_text)) => response_text = processed_text,
+                    Ok(Err(e)) => {
+                        log::error!("Failed to process command: {}", e);
+                        return (StatusCode::INTERNAL_SERVER_ERROR, "internal error")
+                            .into_response();
+                    }
+                    Err(_) => {
+                        tokio::spawn(send_async_reply(
+                            state.clone(),
+                            processing,
+                            params.to.clone(),
+                            params.from.clone(),
+                            maybe_incoming_message_id,
+                        ));
+
+                        return Xml("<Response></Response>").into_response();
+                    }
+                }
             }
         } else {
-            return (axum::http::StatusCode::NOT_FOUND, "not found").into_response();
+            return (StatusCode::NOT_FOUND, "not found").into_response();
         }
     } else {
-        let config = state.config;
-
-        let account_sid = config.twilio_account_sid.to_string();
-        let api_sid = config.twilio_api_key_sid.to_string();
-        let api_secret = config.twilio_api_key_secret.to_string();
-
-        let client = reqwest::Client::new();
-
-        let basic_auth = format!("{}:{}", api_sid, api_secret);
-        let auth_header_value = format!(
-            "Basic {}",
-            general_purpose::STANDARD_NO_PAD.encode(basic_auth)
-        );
-
-        let admin_notification_body = format!("New number: {}", params.from.clone());
-
-        let create_message_body = serde_urlencoded::to_string([
-            ("Body", admin_notification_body.clone()),
-            ("To", config.admin_number.clone()),
-            ("From", config.textabus_number.clone()),
-        ])
-        .expect("Could not encode meeting message creation body");
-
-        client
-            .post(format!(
-                "{}/2010-04-01/Accounts/{}/Messages.json",
-                state.twilio_address, account_sid
-            ))
-            .header("Authorization", auth_header_value.clone())
-            .header("Content-Type", "application/x-www-form-urlencoded")
-            .body(create_message_body)
-            .send()
-            .await
-            .ok();
+        let config = &state.config;
 
-        let admin_message_insertion_result = sqlx::query(
-            r#"
-            INSERT INTO messages (id, origin, destination, body, initial_message_id, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            "#,
+        send_sms(
+            state.sms_sender.as_ref(),
+            &state.db,
+            &config.textabus_number,
+            &config.admin_number,
+            &format!("New number: {}", params.from),
+            maybe_incoming_message_id,
         )
-        .bind(Uuid::new_v4())
-        .bind(config.textabus_number)
-        .bind(config.admin_number)
-        .bind(admin_notification_body)
-        .bind(maybe_incoming_message_id)
-        .bind(Utc::now().naive_utc())
-        .bind(Utc::now().naive_utc())
-        .execute(&state.db)
-        .await;
-
-        if let Err(e) = admin_message_insertion_result {
-            log::error!("Failed to insert admin message: {}", e);
-        }
+        .await
+        .ok();
 
         response_text = "welcome to textabus. we don’t recognise you, please contact a maintainer to join the alpha test.".to_string();
 
@@ -169,13 +247,16 @@ pub async fn get_twilio(
         }
     }
 
+    let response_text = prepare_outbound(&response_text, maybe_number.as_ref().ok());
+
+    let outgoing_message_id = Uuid::new_v4();
     let outgoing_message_insertion_result = sqlx::query(
         r#"
         INSERT INTO messages (id, origin, destination, body, initial_message_id, created_at, updated_at)
         VALUES ($1, $2, $3, $4, $5, $6, $7)
         "#,
     )
-    .bind(Uuid::new_v4())
+    .bind(outgoing_message_id)
     .bind(params.to.clone())
     .bind(params.from.clone())
     .bind(response_text.clone())
@@ -189,16 +270,171 @@ pub async fn get_twilio(
         log::error!("Failed to insert outgoing message: {}", e);
     }
 
+    render_message_response(state, response_text, outgoing_message_id)
+}
+
+// Twilio retries webhooks it got no response to, so the earlier reply is repeated instead of regenerated
+async fn respond_to_retry(state: AppState, params: TwilioParams) -> Response {
+    log::info!("Repeating reply to retried message {}", params.message_sid);
+
+    let previous_reply = sqlx::query_as::<_, Message>(
+        r#"
+        SELECT replies.* FROM messages replies
+        JOIN messages incoming ON replies.initial_message_id = incoming.id
+        WHERE incoming.message_sid = $1
+            AND incoming.initial_message_id IS NULL
+            AND replies.destination = incoming.origin
+        ORDER BY replies.created_at
+        LIMIT 1
+        "#,
+    )
+    .bind(params.message_sid.clone())
+    .fetch_optional(&state.db)
+    .await;
+
+    match previous_reply {
+        // Replaying a reply already sent through the API would deliver it twice
+        Ok(Some(reply)) if reply.sent_via_api => Xml("<Response></Response>").into_response(),
+        Ok(Some(reply)) => render_message_response(state, reply.body, reply.id),
+        // The original request is still being processed or needed no reply
+        Ok(None) => Xml("<Response></Response>").into_response(),
+        Err(e) => {
+            log::error!("Failed to fetch previous reply: {}", e);
+            Xml("<Response></Response>").into_response()
+        }
+    }
+}
+
+fn render_message_response(
+    state: AppState,
+    response_text: String,
+    outgoing_message_id: Uuid,
+) -> Response {
     RenderXml(
         "message-response",
         state.engine,
         MessageResponse {
             body: response_text,
+            // The reply’s SID isn’t known yet, so its status is matched by the message ID
+            status_callback: state
+                .config
+                .root_url
+                .join(&format!(
+                    "/twilio/status?message_id={}",
+                    outgoing_message_id
+                ))
+                .map(|url| url.to_string())
+                .ok(),
         },
     )
     .into_response()
 }
 
+// Delivers a reply that took too long to return in the webhook response
+async fn send_async_reply(
+    state: AppState,
+    processing: JoinHandle<String>,
+    from: String,
+    to: String,
+    maybe_incoming_message_id: Option<Uuid>,
+) {
+    let response_text = match processing.await {
+        Ok(response_text) => response_text,
+        Err(e) => {
+            log::error!("Failed to process command: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = send_sms(
+        state.sms_sender.as_ref(),
+        &state.db,
+        &from,
+        &to,
+        &response_text,
+        maybe_incoming_message_id,
+    )
+    .await
+    {
+        log::error!("Failed to send asynchronous reply: {}", e);
+    }
+}
+
+// Twilio posts each delivery state change, possibly out of order
+#[axum_macros::debug_handler]
+pub async fn post_twilio_status(
+    State(state): State<AppState>,
+    uri: Uri,
+    headers: HeaderMap,
+    Query(status_query): Query<StatusQuery>,
+    body: String,
+) -> Response {
+    let (Ok(form_params), Ok(params)) = (
+        serde_urlencoded::from_str::<Vec<(String, String)>>(&body),
+        serde_urlencoded::from_str::<StatusParams>(&body),
+    ) else {
+        return (StatusCode::UNPROCESSABLE_ENTITY, "invalid parameters").into_response();
+    };
+
+    if !has_valid_signature(&state, &uri, &headers, &form_params) {
+        return (StatusCode::FORBIDDEN, "invalid signature").into_response();
+    }
+
+    let status_update_result = sqlx::query(
+        r#"
+        UPDATE messages
+        SET message_sid = COALESCE(message_sid, $2),
+            delivery_status = $3,
+            delivery_error_code = $4,
+            updated_at = $5
+        WHERE (id = $1 OR ($1 IS NULL AND message_sid = $2))
+            AND (
+                delivery_status IS NULL
+                OR CASE delivery_status
+                    WHEN 'accepted' THEN 0
+                    WHEN 'queued' THEN 1
+                    WHEN 'sending' THEN 2
+                    WHEN 'sent' THEN 3
+                    ELSE 4
+                END <= $6
+            )
+        "#,
+    )
+    .bind(status_query.message_id)
+    .bind(params.message_sid.clone())
+    .bind(params.message_status.clone())
+    .bind(params.error_code)
+    .bind(Utc::now().naive_utc())
+    .bind(delivery_status_rank(&params.message_status))
+    .execute(&state.db)
+    .await;
+
+    match status_update_result {
+        Ok(result) if result.rows_affected() == 0 => {
+            log::warn!(
+                "No message to update for status {} of {}",
+                params.message_status,
+                params.message_sid
+            );
+        }
+        Ok(_) => {}
+        Err(e) => log::error!("Failed to update message status: {}", e),
+    }
+
+    Xml("<Response></Response>").into_response()
+}
+
+// Later states replace earlier ones, final states are never replaced by earlier ones
+fn delivery_status_rank(status: &str) -> i32 {
+    match status {
+        "accepted" => 0,
+        "queued" => 1,
+        "sending" => 2,
+        "sent" => 3,
+        _ => 4,
+    }
+}
+
 #[axum_macros::debug_handler]
 pub async fn get_raw(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
@@ -229,13 +465,16 @@ pub async fn get_raw(
         log::error!("Failed to insert incoming message: {}", e);
     }
 
-    let response_text = process_command(
-        Some(params.body.clone()),
-        &state,
-        &None,
-        maybe_incoming_message_id,
-    )
-    .await;
+    let response_text = prepare_outbound(
+        &process_command(
+            Some(params.body.clone()),
+            &state,
+            &None,
+            maybe_incoming_message_id,
+        )
+        .await,
+        None,
+    );
 
     let outgoing_message_insertion_result = sqlx::query(
         r#"
@@ -260,6 +499,47 @@ pub async fn get_raw(
     response_text
 }
 
+// Twilio signs the public URL, which can differ from the one seen behind a proxy
+fn has_valid_signature(
+    state: &AppState,
+    uri: &Uri,
+    headers: &HeaderMap,
+    form_params: &[(String, String)],
+) -> bool {
+    if !state.config.validate_twilio_signatures {
+        return true;
+    }
+
+    let Some(signature) = headers
+        .get("X-Twilio-Signature")
+        .and_then(|header| header.to_str().ok())
+    else {
+        log::warn!("Rejected unsigned Twilio request");
+        return false;
+    };
+
+    let path_and_query = uri
+        .path_and_query()
+        .map_or(uri.path(), |path_and_query| path_and_query.as_str());
+
+    let Ok(url) = state.config.root_url.join(path_and_query) else {
+        return false;
+    };
+
+    let valid = is_valid_twilio_signature(
+        &state.config.twilio_auth_token,
+        url.as_str(),
+        form_params,
+        signature,
+    );
+
+    if !valid {
+        log::warn!("Rejected Twilio request with invalid signature for {}", url);
+    }
+
+    valid
+}
+
 async fn process_command(
     body: Option<String>,
     state: &AppState,
@@ -270,6 +550,12 @@ async fn process_command(
 
     let command = parse_command(&body);
 
+    if !matches!(command, Command::More(_)) {
+        if let Err(e) = clear_pending_pages(&state.db, number).await {
+            log::error!("Failed to clear overflow: {}", e);
+        }
+    }
+
     match command {
         Command::Stops(stops_command) => handle_stops_request(
             stops_command,
@@ -277,6 +563,7 @@ async fn process_command(
             state.winnipeg_transit_api_address.clone(),
             maybe_incoming_message_id,
             &state.db,
+            number,
         )
         .await
         .unwrap(),
@@ -290,18 +577,89 @@ async fn process_command(
         )
         .await
         .unwrap(),
+        Command::Near(near_command) => handle_near_request(
+            near_command,
+            &state.config,
+            state.winnipeg_transit_api_address.clone(),
+            maybe_incoming_message_id,
+            &state.db,
+            number,
+        )
+        .await
+        .unwrap(),
+        Command::Service(service_command) => handle_service_request(
+            service_command,
+            &state.config,
+            state.winnipeg_transit_api_address.clone(),
+            maybe_incoming_message_id,
+            &state.db,
+            number,
+        )
+        .await
+        .unwrap(),
+        Command::Choice(choice_command) => handle_choice_request(
+            choice_command,
+            &state.config,
+            state.winnipeg_transit_api_address.clone(),
+            maybe_incoming_message_id,
+            &state.db,
+            number,
+        )
+        .await
+        .unwrap(),
         Command::SettingsClock(_settings_clock_command) => {
             handle_settings_clock_request(&state.db, number)
                 .await
                 .unwrap()
         }
-        Command::Help(_help_command) => format!("{}\n{}", HELP_MESSAGE, state.config.root_url),
-        Command::Unknown(_unknown_command) => {
-            format!("{}\n{}", HELP_MESSAGE, state.config.root_url)
+        Command::SettingsFormat(settings_format_command) => {
+            handle_settings_format_request(settings_format_command, &state.db, number)
+                .await
+                .unwrap()
         }
+        Command::SettingsCountdown(settings_countdown_command) => {
+            handle_settings_countdown_request(settings_countdown_command, &state.db, number)
+                .await
+                .unwrap()
+        }
+        Command::SettingsAccents(settings_accents_command) => {
+            handle_settings_accents_request(settings_accents_command, &state.db, number)
+                .await
+                .unwrap()
+        }
+        Command::More(_more_command) => handle_more_request(&state.config, &state.db, number)
+            .await
+            .unwrap(),
+        Command::Help(_help_command) => help_response(state, number).await.unwrap(),
+        Command::Unknown(_unknown_command) => help_response(state, number).await.unwrap(),
     }
 }
 
+// Help is paged like any other long response, a blank line still setting off each section
+async fn help_response(
+    state: &AppState,
+    number: &Option<Number>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut lines = HELP_MESSAGE.lines();
+    let heading = format!("{}\n", lines.next().unwrap_or_default());
+
+    let mut entries: Vec<String> = Vec::new();
+    let mut after_blank_line = false;
+
+    for line in lines.chain(["", state.config.root_url.as_str()]) {
+        if line.is_empty() {
+            after_blank_line = true;
+        } else if after_blank_line {
+            entries.push(format!("\n{}", line));
+            after_blank_line = false;
+        } else {
+            entries.push(line.to_string());
+        }
+    }
+
+    paginate(heading, entries, &state.config, &state.db, number).await
+}
+
 #[serde_as]
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
@@ -311,11 +669,33 @@ pub struct TwilioParams {
     pub message_sid: String,
     pub from: String,
     pub to: String,
+    pub num_media: Option<i32>,
+    #[serde_as(as = "NoneAsEmptyString")]
+    #[serde(default)]
+    pub from_city: Option<String>,
+    pub sms_status: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct MessageResponse {
     body: String,
+    status_callback: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct StatusQuery {
+    pub message_id: Option<Uuid>,
+}
+
+#[serde_as]
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct StatusParams {
+    pub message_sid: String,
+    pub message_status: String,
+    #[serde_as(as = "NoneAsEmptyString")]
+    #[serde(default)]
+    pub error_code: Option<i32>,
 }
 
 #[derive(Deserialize)]
@@ -0,0 +1,109 @@
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+// Twilio signs the full URL it requested followed by any POST parameters sorted by name
+// https://www.twilio.com/docs/usage/security#validating-requests
+
+pub fn compute_twilio_signature(
+    auth_token: &str,
+    url: &str,
+    params: &[(String, String)],
+) -> String {
+    let mac = signed_mac(auth_token, url, params);
+
+    general_purpose::STANDARD.encode(mac.finalize().into_bytes())
+}
+
+pub fn is_valid_twilio_signature(
+    auth_token: &str,
+    url: &str,
+    params: &[(String, String)],
+    signature: &str,
+) -> bool {
+    let Ok(decoded_signature) = general_purpose::STANDARD.decode(signature) else {
+        return false;
+    };
+
+    signed_mac(auth_token, url, params)
+        .verify_slice(&decoded_signature)
+        .is_ok()
+}
+
+fn signed_mac(auth_token: &str, url: &str, params: &[(String, String)]) -> Hmac<Sha1> {
+    let mut mac =
+        Hmac::<Sha1>::new_from_slice(auth_token.as_bytes()).expect("HMAC accepts any key length");
+
+    mac.update(url.as_bytes());
+
+    let mut sorted_params = params.to_vec();
+    sorted_params.sort();
+
+    for (key, value) in sorted_params {
+        mac.update(key.as_bytes());
+        mac.update(value.as_bytes());
+    }
+
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_params() -> Vec<(String, String)> {
+        [
+            ("To", "+18005551212"),
+            ("From", "+12349013030"),
+            ("Digits", "1234"),
+            ("Caller", "+12349013030"),
+            ("CallSid", "CA1234567890ABCDE"),
+        ]
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn test_compute_twilio_signature() {
+        assert_eq!(
+            compute_twilio_signature(
+                "12345",
+                "https://mycompany.com/myapp.php?foo=1&bar=2",
+                &example_params()
+            ),
+            "0/KCTR6DLpKmkAf8muzZqo1nDgQ="
+        );
+    }
+
+    #[test]
+    fn test_is_valid_twilio_signature() {
+        let url = "https://mycompany.com/myapp.php?foo=1&bar=2";
+        let params = example_params();
+
+        assert!(is_valid_twilio_signature(
+            "12345",
+            url,
+            &params,
+            "0/KCTR6DLpKmkAf8muzZqo1nDgQ="
+        ));
+        assert!(!is_valid_twilio_signature(
+            "54321",
+            url,
+            &params,
+            "0/KCTR6DLpKmkAf8muzZqo1nDgQ="
+        ));
+        assert!(!is_valid_twilio_signature(
+            "12345",
+            "https://mycompany.com/myapp.php?foo=1&bar=3",
+            &params,
+            "0/KCTR6DLpKmkAf8muzZqo1nDgQ="
+        ));
+        assert!(!is_valid_twilio_signature(
+            "12345",
+            url,
+            &params,
+            "not base64!"
+        ));
+    }
+}
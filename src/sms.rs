@@ -0,0 +1,402 @@
+use axum::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+use sqlx::{types::Uuid, PgPool};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::mpsc;
+
+use crate::{config::Config, encoding::prepare_outbound, models::Number};
+
+#[async_trait]
+pub trait SmsSender: Send + Sync {
+    // Returns the provider’s message SID when it supplies one
+    async fn send(&self, from: &str, to: &str, body: &str) -> Result<Option<String>, String>;
+}
+
+pub struct TwilioSmsSender {
+    address: String,
+    account_sid: String,
+    api_key_sid: String,
+    api_key_secret: String,
+    status_callback: String,
+}
+
+impl TwilioSmsSender {
+    pub fn new(address: String, config: &Config) -> Self {
+        TwilioSmsSender {
+            address,
+            account_sid: config.twilio_account_sid.clone(),
+            api_key_sid: config.twilio_api_key_sid.clone(),
+            api_key_secret: config.twilio_api_key_secret.clone(),
+            status_callback: config
+                .root_url
+                .join("/twilio/status")
+                .expect("Unable to build status callback URL")
+                .to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl SmsSender for TwilioSmsSender {
+    async fn send(&self, from: &str, to: &str, body: &str) -> Result<Option<String>, String> {
+        let create_message_body = serde_urlencoded::to_string([
+            ("Body", body),
+            ("To", to),
+            ("From", from),
+            ("StatusCallback", &self.status_callback),
+        ])
+        .map_err(|e| format!("Could not encode message creation body: {}", e))?;
+
+        let response = reqwest::Client::new()
+            .post(format!(
+                "{}/2010-04-01/Accounts/{}/Messages.json",
+                self.address, self.account_sid
+            ))
+            .basic_auth(&self.api_key_sid, Some(&self.api_key_secret))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(create_message_body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let status = response.status();
+        let response_text = response.text().await.map_err(|e| e.to_string())?;
+        let twilio_response: TwilioMessageResponse =
+            serde_json::from_str(&response_text).unwrap_or_default();
+
+        if status.is_success() {
+            Ok(twilio_response.sid)
+        } else {
+            Err(match (twilio_response.code, twilio_response.message) {
+                (Some(code), Some(message)) => format!("{} {}", code, message),
+                _ => format!("{} {}", status, response_text),
+            })
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TwilioMessageResponse {
+    sid: Option<String>,
+    code: Option<i64>,
+    message: Option<String>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedSms {
+    pub from: String,
+    pub to: String,
+    pub body: String,
+}
+
+// Keeps sends in memory instead of contacting a provider, optionally failing them all
+#[cfg(any(test, feature = "test-support"))]
+#[derive(Default)]
+pub struct RecordingSmsSender {
+    pub sent: std::sync::Mutex<Vec<RecordedSms>>,
+    pub failure: Option<String>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl RecordingSmsSender {
+    pub fn failing(failure: &str) -> Self {
+        RecordingSmsSender {
+            failure: Some(failure.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+#[async_trait]
+impl SmsSender for RecordingSmsSender {
+    async fn send(&self, from: &str, to: &str, body: &str) -> Result<Option<String>, String> {
+        let mut sent = self.sent.lock().unwrap();
+
+        sent.push(RecordedSms {
+            from: from.to_string(),
+            to: to.to_string(),
+            body: body.to_string(),
+        });
+
+        match &self.failure {
+            Some(failure) => Err(failure.clone()),
+            None => Ok(Some(format!("SMFAKE{}", sent.len()))),
+        }
+    }
+}
+
+// Sends a message and records it with the provider’s SID or the error that prevented it
+pub async fn send_sms(
+    sender: &dyn SmsSender,
+    db: &PgPool,
+    from: &str,
+    to: &str,
+    body: &str,
+    initial_message_id: Option<Uuid>,
+) -> Result<Option<String>, String> {
+    let number = sqlx::query_as::<_, Number>(
+        r#"
+        SELECT * FROM numbers
+        WHERE number = $1
+        "#,
+    )
+    .bind(to)
+    .fetch_optional(db)
+    .await
+    .unwrap_or_else(|e| {
+        log::error!("Failed to fetch number: {}", e);
+        None
+    });
+
+    let body = &prepare_outbound(body, number.as_ref());
+
+    let send_result = if number.as_ref().is_some_and(|number| number.opted_out) {
+        Err("recipient opted out".to_string())
+    } else {
+        sender.send(from, to, body).await
+    };
+
+    if let Err(e) = &send_result {
+        log::error!("Failed to send message to {}: {}", to, e);
+    }
+
+    let message_insertion_result = sqlx::query(
+        r#"
+        INSERT INTO messages (id, message_sid, origin, destination, body, initial_message_id, send_error, sent_via_api, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, TRUE, $8, $9)
+        "#,
+    )
+    .bind(Uuid::new_v4())
+    .bind(send_result.as_ref().ok().cloned().flatten())
+    .bind(from)
+    .bind(to)
+    .bind(body)
+    .bind(initial_message_id)
+    .bind(send_result.as_ref().err())
+    .bind(Utc::now().naive_utc())
+    .bind(Utc::now().naive_utc())
+    .execute(db)
+    .await;
+
+    if let Err(e) = message_insertion_result {
+        log::error!("Failed to insert sent message: {}", e);
+    }
+
+    send_result
+}
+
+#[derive(Debug)]
+pub struct OutboundSms {
+    pub from: String,
+    pub to: String,
+    pub body: String,
+}
+
+// Sends queued messages one at a time, pausing between them to stay within the provider’s rate
+#[derive(Clone)]
+pub struct OutboundQueue {
+    queue: mpsc::UnboundedSender<OutboundSms>,
+}
+
+impl OutboundQueue {
+    pub fn start(sender: Arc<dyn SmsSender>, db: PgPool, interval: Duration) -> Self {
+        let (queue, mut receiver) = mpsc::unbounded_channel::<OutboundSms>();
+
+        tokio::spawn(async move {
+            while let Some(sms) = receiver.recv().await {
+                send_sms(sender.as_ref(), &db, &sms.from, &sms.to, &sms.body, None)
+                    .await
+                    .ok();
+
+                tokio::time::sleep(interval).await;
+            }
+        });
+
+        OutboundQueue { queue }
+    }
+
+    pub fn enqueue(&self, sms: OutboundSms) -> Result<(), String> {
+        self.queue.send(sms).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Message;
+    use wiremock::matchers::{basic_auth, body_string, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn twilio_sender(address: String) -> TwilioSmsSender {
+        TwilioSmsSender {
+            address,
+            account_sid: "AC123".to_string(),
+            api_key_sid: "SK456".to_string(),
+            api_key_secret: "secret".to_string(),
+            status_callback: "https://example.com/twilio/status".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_twilio_send_returns_the_message_sid() {
+        let mock_twilio = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/2010-04-01/Accounts/AC123/Messages.json"))
+            .and(basic_auth("SK456", "secret"))
+            .and(header("Content-Type", "application/x-www-form-urlencoded"))
+            .and(body_string(
+                "Body=10907+%26+10901+%E2%9C%93&To=%2B12045551234&From=%2B12045550000\
+                &StatusCallback=https%3A%2F%2Fexample.com%2Ftwilio%2Fstatus",
+            ))
+            .respond_with(ResponseTemplate::new(201).set_body_string(r#"{"sid": "SM789"}"#))
+            .expect(1)
+            .mount(&mock_twilio)
+            .await;
+
+        let result = twilio_sender(mock_twilio.uri())
+            .send("+12045550000", "+12045551234", "10907 & 10901 ✓")
+            .await;
+
+        assert_eq!(result, Ok(Some("SM789".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_twilio_send_reports_the_error_code_and_message() {
+        let mock_twilio = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/2010-04-01/Accounts/AC123/Messages.json"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(
+                r#"{"code": 21211, "message": "Invalid 'To' Phone Number", "status": 400}"#,
+            ))
+            .mount(&mock_twilio)
+            .await;
+
+        let result = twilio_sender(mock_twilio.uri())
+            .send("+12045550000", "nonsense", "hello")
+            .await;
+
+        assert_eq!(result, Err("21211 Invalid 'To' Phone Number".to_string()));
+    }
+
+    #[sqlx::test]
+    async fn test_send_sms_records_the_provider_sid(db: PgPool) {
+        let sender = RecordingSmsSender::default();
+
+        let result = send_sms(&sender, &db, "textabus", "approved", "hello", None).await;
+
+        assert_eq!(result, Ok(Some("SMFAKE1".to_string())));
+        assert_eq!(
+            *sender.sent.lock().unwrap(),
+            vec![RecordedSms {
+                from: "textabus".to_string(),
+                to: "approved".to_string(),
+                body: "hello".to_string(),
+            }]
+        );
+
+        let message: Message = sqlx::query_as("SELECT * FROM messages")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+
+        assert_eq!(message.message_sid, Some("SMFAKE1".to_string()));
+        assert_eq!(message.destination, "approved");
+        assert_eq!(message.send_error, None);
+    }
+
+    #[sqlx::test]
+    async fn test_send_sms_records_the_error(db: PgPool) {
+        let sender = RecordingSmsSender::failing("21211 Invalid 'To' Phone Number");
+
+        let result = send_sms(&sender, &db, "textabus", "approved", "hello", None).await;
+
+        assert!(result.is_err());
+
+        let message: Message = sqlx::query_as("SELECT * FROM messages")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+
+        assert_eq!(message.message_sid, None);
+        assert_eq!(
+            message.send_error,
+            Some("21211 Invalid 'To' Phone Number".to_string())
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_send_sms_does_not_send_to_an_opted_out_number(db: PgPool) {
+        sqlx::query(
+            "INSERT INTO numbers (number, opted_out, created_at, updated_at)
+            VALUES ('approved', true, NOW(), NOW())",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        let sender = RecordingSmsSender::default();
+
+        let result = send_sms(&sender, &db, "textabus", "approved", "hello", None).await;
+
+        assert_eq!(result, Err("recipient opted out".to_string()));
+        assert!(sender.sent.lock().unwrap().is_empty());
+
+        let message: Message = sqlx::query_as("SELECT * FROM messages")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+
+        assert_eq!(message.send_error, Some("recipient opted out".to_string()));
+    }
+
+    #[sqlx::test]
+    async fn test_outbound_queue_spaces_out_sends(db: PgPool) {
+        let sender = Arc::new(RecordingSmsSender::default());
+        let queue = OutboundQueue::start(sender.clone(), db.clone(), Duration::from_millis(100));
+
+        let started_at = std::time::Instant::now();
+
+        for to in ["one", "two", "three"] {
+            queue
+                .enqueue(OutboundSms {
+                    from: "textabus".to_string(),
+                    to: to.to_string(),
+                    body: "hello".to_string(),
+                })
+                .unwrap();
+        }
+
+        let mut message_count: i64 = 0;
+
+        for _ in 0..50 {
+            message_count = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+
+            if message_count == 3 {
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        assert_eq!(message_count, 3);
+        assert!(started_at.elapsed() >= Duration::from_millis(200));
+
+        let recipients: Vec<String> = sender
+            .sent
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|sms| sms.to.clone())
+            .collect();
+
+        assert_eq!(recipients, vec!["one", "two", "three"]);
+    }
+}
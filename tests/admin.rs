@@ -6,13 +6,15 @@ use select::{
     document::Document,
     predicate::{Attr, Class, Descendant, Name, Predicate},
 };
-use serde_json::json;
 use speculoos::prelude::*;
 use sqlx::postgres::PgPool;
-use textabus::{models::Message, routes::get_composed_approval_message, InjectableServices};
-use wiremock::{
-    matchers::{body_string, method, path_regex},
-    Mock, MockServer, ResponseTemplate,
+use std::sync::Arc;
+use textabus::{
+    encoding::transliterate,
+    models::{Alias, Message},
+    routes::get_composed_approval_message,
+    sms::{RecordedSms, RecordingSmsSender},
+    InjectableServices,
 };
 
 #[sqlx::test(fixtures("numbers-approved", "messages"))]
@@ -21,6 +23,7 @@ async fn admin_serves_message_history(db: PgPool) {
         "/admin/messages",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: None,
         },
@@ -95,12 +98,80 @@ async fn admin_serves_message_history(db: PgPool) {
     assert_that(&oldest_last_message.text()).contains("?");
 }
 
+#[sqlx::test(fixtures("numbers-approved", "messages"))]
+async fn admin_shows_message_delivery(db: PgPool) {
+    sqlx::query(
+        "UPDATE messages SET delivery_status = 'delivered' WHERE id = '4addcf7f-dd8a-4cd8-94ca-e37c7d9f6519'",
+    )
+    .execute(&db)
+    .await
+    .expect("Failed to set delivery status");
+
+    sqlx::query(
+        "UPDATE messages SET delivery_status = 'undelivered', delivery_error_code = 30006 WHERE id = 'b3bea420-3783-4abc-9f66-ed9007e698e8'",
+    )
+    .execute(&db)
+    .await
+    .expect("Failed to set delivery status");
+
+    sqlx::query(
+        "UPDATE messages SET send_error = '21211 Invalid number' WHERE id = '618e7375-932e-4a11-b472-e608d4e28139'",
+    )
+    .execute(&db)
+    .await
+    .expect("Failed to set send error");
+
+    let response = get_with_auth(
+        "/admin/messages",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+
+    let delivery_of = |id: &str| {
+        document
+            .find(Attr("data-id", id))
+            .next()
+            .unwrap()
+            .find(Class("delivery"))
+            .next()
+            .map(|delivery| {
+                (
+                    delivery.attr("data-status").unwrap().to_string(),
+                    delivery.text(),
+                )
+            })
+    };
+
+    assert_eq!(
+        delivery_of("4addcf7f-dd8a-4cd8-94ca-e37c7d9f6519"),
+        Some(("delivered".to_string(), "delivered".to_string()))
+    );
+    assert_eq!(
+        delivery_of("b3bea420-3783-4abc-9f66-ed9007e698e8"),
+        Some(("undelivered".to_string(), "undelivered 30006".to_string()))
+    );
+    assert_eq!(
+        delivery_of("618e7375-932e-4a11-b472-e608d4e28139"),
+        Some(("unsent".to_string(), "not sent".to_string()))
+    );
+    assert_eq!(delivery_of("8a8c40e1-e7b6-497b-9cca-550665f48922"), None);
+}
+
 #[sqlx::test(fixtures("numbers-approved", "numbers-unapproved"))]
 async fn admin_serves_number_listings(db: PgPool) {
     let response = get_with_auth(
         "/admin/numbers",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: None,
         },
@@ -138,32 +209,17 @@ async fn admin_serves_number_listings(db: PgPool) {
 async fn test_approve_unapproved_number(db: PgPool) {
     let config = get_config();
 
-    let mock_twilio: MockServer = MockServer::start().await;
-
-    let approval_body = get_composed_approval_message();
-
-    let twilio_create_message_body = serde_urlencoded::to_string([
-        ("Body", &approval_body),
-        ("To", &"unapproved".to_string()),
-        ("From", &config.textabus_number),
-    ])
-    .expect("Could not encode message creation body");
+    let sms_sender = Arc::new(RecordingSmsSender::default());
 
-    Mock::given(method("POST"))
-        .and(path_regex(r"^/2010-04-01/Accounts/.*/Messages.json$"))
-        .and(body_string(twilio_create_message_body.to_string()))
-        .respond_with(ResponseTemplate::new(201).set_body_json(json!({})))
-        .expect(1)
-        .named("create message")
-        .mount(&mock_twilio)
-        .await;
+    let approval_body = transliterate(&get_composed_approval_message());
 
     let response = post_with_auth(
         "/admin/numbers/unapproved/approve",
         "",
         InjectableServices {
             db: db.clone(),
-            twilio_address: Some(mock_twilio.uri()),
+            sms_sender: Some(sms_sender.clone()),
+            twilio_address: None,
             winnipeg_transit_api_address: None,
         },
     )
@@ -192,6 +248,113 @@ async fn test_approve_unapproved_number(db: PgPool) {
     assert_eq!(approval_message.origin, config.textabus_number);
     assert_eq!(approval_message.destination, "unapproved");
     assert_eq!(approval_message.initial_message_id, None,);
+    assert_eq!(approval_message.message_sid, Some("SMFAKE1".to_string()));
+    assert_eq!(approval_message.send_error, None);
+
+    assert_eq!(
+        *sms_sender.sent.lock().unwrap(),
+        vec![RecordedSms {
+            from: config.textabus_number.clone(),
+            to: "unapproved".to_string(),
+            body: approval_body,
+        }]
+    );
+}
+
+#[sqlx::test(fixtures("numbers-approved", "numbers-opted-out"))]
+async fn admin_shows_opted_out_numbers(db: PgPool) {
+    let response = get_with_auth(
+        "/admin/numbers",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+
+    let opted_out_row = document
+        .find(Name("tr").and(Attr("data-opted-out", "")))
+        .next()
+        .unwrap();
+    assert_eq!(opted_out_row.attr("data-number").unwrap(), "approved");
+    assert_that(&opted_out_row.find(Class("opt-out")).next().unwrap().text()).contains("opted out");
+}
+
+#[sqlx::test(fixtures("numbers-unapproved"))]
+async fn test_approve_does_not_text_an_opted_out_number(db: PgPool) {
+    sqlx::query("UPDATE numbers SET opted_out = true WHERE number = 'unapproved'")
+        .execute(&db)
+        .await
+        .expect("Failed to opt out number");
+
+    let sms_sender = Arc::new(RecordingSmsSender::default());
+
+    let response = post_with_auth(
+        "/admin/numbers/unapproved/approve",
+        "",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: Some(sms_sender.clone()),
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let approval_message: Message = sqlx::query_as("SELECT * FROM messages")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to fetch message");
+
+    assert_eq!(
+        approval_message.send_error,
+        Some("recipient opted out".to_string())
+    );
+
+    assert!(sms_sender.sent.lock().unwrap().is_empty());
+}
+
+#[sqlx::test(fixtures("numbers-unapproved"))]
+async fn test_approve_records_a_failed_approval_message(db: PgPool) {
+    let sms_sender = Arc::new(RecordingSmsSender::failing(
+        "21211 The 'To' number unapproved is not a valid phone number.",
+    ));
+
+    let response = post_with_auth(
+        "/admin/numbers/unapproved/approve",
+        "",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: Some(sms_sender.clone()),
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let [approval_message]: [Message; 1] = sqlx::query_as("SELECT * FROM messages")
+        .fetch_all(&db)
+        .await
+        .expect("Failed to fetch messages")
+        .try_into()
+        .expect("Expected exactly 1 message");
+
+    assert_eq!(approval_message.message_sid, None);
+    assert_eq!(
+        approval_message.send_error,
+        Some("21211 The 'To' number unapproved is not a valid phone number.".to_string())
+    );
 }
 
 #[sqlx::test(fixtures("numbers-approved"))]
@@ -201,6 +364,7 @@ async fn test_unapprove_approved_number(db: PgPool) {
         "",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: None,
         },
@@ -219,12 +383,200 @@ async fn test_unapprove_approved_number(db: PgPool) {
     assert_eq!(approved_count, 0);
 }
 
+#[sqlx::test(fixtures("aliases"))]
+async fn admin_serves_alias_listings(db: PgPool) {
+    let response = get_with_auth(
+        "/admin/aliases",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let rows: Vec<_> = document
+        .find(Descendant(Name("tbody"), Name("tr")))
+        .collect();
+
+    assert_eq!(rows.len(), 2);
+
+    assert_that(&rows[0].text()).contains("the forks");
+    assert_that(&rows[0].text()).contains("49.88895,-97.13424");
+
+    assert_that(&rows[1].text()).contains("train station");
+    assert_that(&rows[1].text()).contains("Union Station");
+
+    let edit_link = rows[1].find(Name("a")).next().unwrap();
+    assert_eq!(
+        edit_link.attr("href").unwrap(),
+        "/admin/aliases/0a8f6a4e-4c1e-4d1b-9a47-3f0a1f6f2c02"
+    );
+}
+
+#[sqlx::test]
+async fn admin_creates_an_alias(db: PgPool) {
+    let response = post_with_auth(
+        "/admin/aliases",
+        "name=HSC&query=Health+Sciences+Centre&latitude=&longitude=",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let [alias]: [Alias; 1] = sqlx::query_as("SELECT * FROM aliases")
+        .fetch_all(&db)
+        .await
+        .expect("Failed to fetch aliases")
+        .try_into()
+        .expect("Expected exactly 1 alias");
+
+    assert_eq!(alias.name, "HSC");
+    assert_eq!(alias.query, Some("Health Sciences Centre".to_string()));
+    assert_eq!(alias.latitude, None);
+    assert_eq!(alias.longitude, None);
+}
+
+#[sqlx::test]
+async fn admin_rejects_an_alias_without_a_location(db: PgPool) {
+    let response = post_with_auth(
+        "/admin/aliases",
+        "name=HSC&query=&latitude=49.9&longitude=",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert_eq!(response.status(), 422);
+
+    let alias_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM aliases")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to fetch alias count");
+
+    assert_eq!(alias_count, 0);
+}
+
+#[sqlx::test(fixtures("aliases"))]
+async fn admin_rejects_a_duplicate_alias_name(db: PgPool) {
+    let response = post_with_auth(
+        "/admin/aliases",
+        "name=The+Forks&query=Forks+Market",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert_eq!(response.status(), 422);
+}
+
+#[sqlx::test]
+async fn admin_reports_other_alias_failures_as_server_errors(db: PgPool) {
+    // Longer than the name column allows
+    let long_name = "a".repeat(256);
+
+    let response = post_with_auth(
+        "/admin/aliases",
+        &format!("name={}&query=Forks+Market", long_name),
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert_eq!(response.status(), 500);
+}
+
+#[sqlx::test(fixtures("aliases"))]
+async fn admin_serves_an_alias_edit_form(db: PgPool) {
+    let response = get_with_auth(
+        "/admin/aliases/0a8f6a4e-4c1e-4d1b-9a47-3f0a1f6f2c01",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+
+    let name_input = document
+        .find(Name("input").and(Attr("name", "name")))
+        .next()
+        .unwrap();
+    assert_eq!(name_input.attr("value").unwrap(), "the forks");
+
+    let latitude_input = document
+        .find(Name("input").and(Attr("name", "latitude")))
+        .next()
+        .unwrap();
+    assert_eq!(latitude_input.attr("value").unwrap(), "49.88895");
+}
+
+#[sqlx::test(fixtures("aliases"))]
+async fn admin_updates_an_alias(db: PgPool) {
+    let response = post_with_auth(
+        "/admin/aliases/0a8f6a4e-4c1e-4d1b-9a47-3f0a1f6f2c01",
+        "name=the+forks&query=&latitude=49.8869&longitude=-97.1306",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let alias: Alias = sqlx::query_as("SELECT * FROM aliases WHERE name = 'the forks'")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to fetch alias");
+
+    assert_eq!(alias.latitude, Some(49.8869));
+    assert_eq!(alias.longitude, Some(-97.1306));
+}
+
 #[sqlx::test]
 async fn admin_rejects_without_auth(db: PgPool) {
     let messages_response = get(
         "/admin/messages",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: None,
         },
@@ -238,6 +590,7 @@ async fn admin_rejects_without_auth(db: PgPool) {
         "/admin/numbers",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: None,
         },
@@ -246,4 +599,155 @@ async fn admin_rejects_without_auth(db: PgPool) {
     .expect("Failed to execute request");
 
     assert_eq!(numbers_response.status(), 401);
+
+    let aliases_response = get(
+        "/admin/aliases",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert_eq!(aliases_response.status(), 401);
+}
+
+async fn add_opted_out_approved_number(db: &PgPool) {
+    sqlx::query(
+        "INSERT INTO numbers (number, approved, opted_out, created_at, updated_at)
+        VALUES ('quiet', true, true, NOW(), NOW())",
+    )
+    .execute(db)
+    .await
+    .expect("Failed to insert number");
+}
+
+#[sqlx::test(fixtures("numbers-approved", "numbers-unapproved"))]
+async fn admin_previews_announcement_segments(db: PgPool) {
+    add_opted_out_approved_number(&db).await;
+
+    let body = serde_urlencoded::to_string([
+        (
+            "body",
+            "textabus now uses the v4 API. Times should be more accurate, and “stops” lists each stop’s direction",
+        ),
+        ("action", "preview"),
+    ])
+    .unwrap();
+
+    let response = post_with_auth(
+        "/admin/announcements",
+        &body,
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let preview = document.find(Class("preview")).next().unwrap();
+
+    assert_that(&preview.find(Name("pre")).next().unwrap().text())
+        .contains("\"stops\" lists each stop's direction");
+    assert_eq!(preview.find(Class("segments")).next().unwrap().text(), "1");
+    assert_eq!(
+        preview
+            .find(Class("accents-segments"))
+            .next()
+            .unwrap()
+            .text(),
+        "2"
+    );
+    assert_eq!(
+        preview.find(Class("recipients")).next().unwrap().text(),
+        "1"
+    );
+
+    let message_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to fetch message count");
+
+    assert_eq!(message_count, 0);
+}
+
+#[sqlx::test(fixtures("numbers-approved", "numbers-unapproved"))]
+async fn admin_sends_announcement_to_approved_numbers(db: PgPool) {
+    add_opted_out_approved_number(&db).await;
+
+    let sms_sender = Arc::new(RecordingSmsSender::default());
+
+    let response = post_with_auth(
+        "/admin/announcements",
+        "body=new+command%3A+more&action=send",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: Some(sms_sender.clone()),
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let mut messages: Vec<Message> = vec![];
+
+    for _ in 0..50 {
+        messages = sqlx::query_as("SELECT * FROM messages")
+            .fetch_all(&db)
+            .await
+            .expect("Failed to fetch messages");
+
+        if !messages.is_empty() {
+            break;
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    let [announcement]: [Message; 1] = messages.try_into().expect("Expected exactly 1 message");
+
+    assert_eq!(announcement.body, "new command: more");
+    assert_eq!(announcement.destination, "approved");
+    assert_eq!(announcement.message_sid, Some("SMFAKE1".to_string()));
+    assert_eq!(announcement.send_error, None);
+
+    let recipients: Vec<String> = sms_sender
+        .sent
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|sms| sms.to.clone())
+        .collect();
+
+    assert_eq!(recipients, vec!["approved"]);
+}
+
+#[sqlx::test]
+async fn admin_rejects_an_empty_announcement(db: PgPool) {
+    let response = post_with_auth(
+        "/admin/announcements",
+        "body=+&action=send",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert_eq!(response.status(), 422);
 }
@@ -0,0 +1,163 @@
+mod helpers;
+
+use helpers::get;
+
+use speculoos::prelude::*;
+use sqlx::postgres::PgPool;
+use std::{env, fs, sync::Arc, time::Duration};
+use textabus::{
+    models::Message,
+    sms::{RecordedSms, RecordingSmsSender},
+    InjectableServices,
+};
+use wiremock::matchers::{method, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// Every test in this file shares the lowered threshold, so setting it is race-free
+fn lower_async_reply_threshold() {
+    env::set_var("ASYNC_REPLY_THRESHOLD_MS", "100");
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn slow_command_replies_through_the_messages_api(db: PgPool) {
+    lower_async_reply_threshold();
+
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(mock_stop_schedule_response)
+                .set_delay(Duration::from_millis(500)),
+        )
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let sms_sender = Arc::new(RecordingSmsSender::default());
+
+    let response = get(
+        "/twilio?Body=10619&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: Some(sms_sender.clone()),
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+    assert_eq!(response.headers()["content-type"], "text/xml");
+    assert_eq!(response.text().await.unwrap(), "<Response></Response>");
+
+    let mut messages: Vec<Message> = vec![];
+
+    for _ in 0..50 {
+        messages = sqlx::query_as("SELECT * FROM messages ORDER BY created_at")
+            .fetch_all(&db)
+            .await
+            .expect("Failed to fetch messages");
+
+        if messages.len() == 2 {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let [incoming_message, outgoing_message]: [Message; 2] =
+        messages.try_into().expect("Expected exactly 2 messages");
+
+    assert_eq!(incoming_message.body, "10619");
+
+    assert_that(&outgoing_message.body).starts_with("10619 WB Graham@Vaughan (The Bay)");
+    assert_eq!(outgoing_message.origin, "textabus");
+    assert_eq!(outgoing_message.destination, "approved");
+    assert_eq!(outgoing_message.message_sid, Some("SMFAKE1".to_string()));
+    assert_eq!(
+        outgoing_message.initial_message_id,
+        Some(incoming_message.id)
+    );
+
+    let [reply]: [RecordedSms; 1] = sms_sender
+        .sent
+        .lock()
+        .unwrap()
+        .clone()
+        .try_into()
+        .expect("Expected exactly 1 sent message");
+
+    assert_eq!(reply.from, "textabus");
+    assert_eq!(reply.to, "approved");
+    assert_eq!(reply.body, outgoing_message.body);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn retried_slow_command_is_not_replied_to_twice(db: PgPool) {
+    lower_async_reply_threshold();
+
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(mock_stop_schedule_response)
+                .set_delay(Duration::from_millis(500)),
+        )
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let sms_sender = Arc::new(RecordingSmsSender::default());
+    let services = || InjectableServices {
+        db: db.clone(),
+        sms_sender: Some(sms_sender.clone()),
+        twilio_address: None,
+        winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+    };
+
+    get(
+        "/twilio?Body=10619&From=approved&To=textabus&MessageSid=SM1849",
+        services(),
+    )
+    .await
+    .expect("Failed to execute request");
+
+    for _ in 0..50 {
+        if sms_sender.sent.lock().unwrap().len() == 1 {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let retry_response = get(
+        "/twilio?Body=10619&From=approved&To=textabus&MessageSid=SM1849",
+        services(),
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(retry_response.status().is_success());
+    assert_eq!(
+        retry_response.text().await.unwrap(),
+        "<Response></Response>"
+    );
+
+    assert_eq!(sms_sender.sent.lock().unwrap().len(), 1);
+
+    let message_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to fetch message count");
+
+    assert_eq!(message_count, 2);
+}
@@ -0,0 +1,21 @@
+INSERT INTO
+    aliases (id, name, query, latitude, longitude, created_at, updated_at)
+VALUES
+    (
+        '0a8f6a4e-4c1e-4d1b-9a47-3f0a1f6f2c01',
+        'the forks',
+        NULL,
+        49.88895,
+        -97.13424,
+        NOW(),
+        NOW()
+    ),
+    (
+        '0a8f6a4e-4c1e-4d1b-9a47-3f0a1f6f2c02',
+        'train station',
+        'Union Station',
+        NULL,
+        NULL,
+        NOW(),
+        NOW()
+    );
\ No newline at end of file
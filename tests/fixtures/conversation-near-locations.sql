@@ -0,0 +1,10 @@
+INSERT INTO
+    conversation_states (number, state, expires_at, created_at, updated_at)
+VALUES
+    (
+        'approved',
+        '{"type": "locations", "routes": ["blue"], "near": true, "locations": [{"name": "245 SmithSt", "latitude": 49.89218, "longitude": -97.14084}, {"name": "Via Rail Station (Union Station) (123 MainSt)", "latitude": 49.88895, "longitude": -97.13424}]}',
+        NOW() + INTERVAL '10 minutes',
+        NOW(),
+        NOW()
+    );
@@ -0,0 +1,10 @@
+INSERT INTO
+    conversation_states (number, state, expires_at, created_at, updated_at)
+VALUES
+    (
+        'approved',
+        '{"type": "stops", "stop_numbers": [10625, 10619]}',
+        NOW() + INTERVAL '10 minutes',
+        NOW(),
+        NOW()
+    );
\ No newline at end of file
@@ -0,0 +1,6 @@
+UPDATE
+    numbers
+SET
+    accents = true
+WHERE
+    number = 'approved';
\ No newline at end of file
@@ -0,0 +1,6 @@
+UPDATE
+    numbers
+SET
+    countdown_horizon = 10
+WHERE
+    number = 'approved';
\ No newline at end of file
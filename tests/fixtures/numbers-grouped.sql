@@ -0,0 +1,6 @@
+UPDATE
+    numbers
+SET
+    times_format = 'grouped'
+WHERE
+    number = 'approved';
\ No newline at end of file
@@ -0,0 +1,6 @@
+UPDATE
+    numbers
+SET
+    times_format = 'headway'
+WHERE
+    number = 'approved';
\ No newline at end of file
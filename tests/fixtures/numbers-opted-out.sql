@@ -0,0 +1,6 @@
+UPDATE
+    numbers
+SET
+    opted_out = true
+WHERE
+    number = 'approved';
\ No newline at end of file
@@ -1,10 +1,10 @@
 use base64::{engine::general_purpose, Engine as _};
 use reqwest::Client;
-use std::env;
-use textabus::{app, InjectableServices};
+use std::{collections::HashMap, env, fs};
+use textabus::{app, signature::compute_twilio_signature, InjectableServices};
 use tokio::net::TcpListener;
-use wiremock::matchers::any;
-use wiremock::{Mock, MockServer, ResponseTemplate};
+use wiremock::matchers::{any, method, path, path_regex, query_param};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
 
 use textabus::config::{ConfigProvider, EnvVarProvider};
 
@@ -18,8 +18,43 @@ pub fn get_config() -> textabus::config::Config {
     env_config_provider.get_config().clone()
 }
 
+#[allow(dead_code)]
 pub async fn get(
     path: &str,
+    services: InjectableServices,
+) -> Result<reqwest::Response, reqwest::Error> {
+    let signature = sign(path);
+
+    get_with_signature(path, Some(&signature), services).await
+}
+
+#[allow(dead_code)]
+pub async fn get_with_signature(
+    path: &str,
+    signature: Option<&str>,
+    mut services: InjectableServices,
+) -> Result<reqwest::Response, reqwest::Error> {
+    services = set_up_services(services).await;
+
+    let app_address = spawn_app(services).await.address;
+
+    let client = Client::new();
+    let url = format!("{}{}", app_address, path);
+
+    let mut request = client.get(&url);
+
+    if let Some(signature) = signature {
+        request = request.header("X-Twilio-Signature", signature);
+    }
+
+    request.send().await
+}
+
+#[allow(dead_code)]
+pub async fn post_form(
+    path: &str,
+    params: &[(&str, &str)],
+    signature: Option<&str>,
     mut services: InjectableServices,
 ) -> Result<reqwest::Response, reqwest::Error> {
     services = set_up_services(services).await;
@@ -29,7 +64,41 @@ pub async fn get(
     let client = Client::new();
     let url = format!("{}{}", app_address, path);
 
-    client.get(&url).send().await
+    let mut request = client.post(&url).form(params);
+
+    if let Some(signature) = signature {
+        request = request.header("X-Twilio-Signature", signature);
+    }
+
+    request.send().await
+}
+
+#[allow(dead_code)]
+pub fn sign_form(path: &str, params: &[(&str, &str)]) -> String {
+    let config = get_config();
+    let url = config
+        .root_url
+        .join(path)
+        .expect("Failed to join path to root URL");
+
+    let params: Vec<(String, String)> = params
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+    compute_twilio_signature(&config.twilio_auth_token, url.as_str(), &params)
+}
+
+// Signs as Twilio would for the public URL, using the fixed auth token from .env.test
+#[allow(dead_code)]
+pub fn sign(path: &str) -> String {
+    let config = get_config();
+    let url = config
+        .root_url
+        .join(path)
+        .expect("Failed to join path to root URL");
+
+    compute_twilio_signature(&config.twilio_auth_token, url.as_str(), &[])
 }
 
 #[allow(dead_code)]
@@ -85,6 +154,7 @@ pub async fn post_with_auth(
                 general_purpose::STANDARD.encode(config.auth.clone())
             ),
         )
+        .header("Content-Type", "application/x-www-form-urlencoded")
         .body(body.to_string())
         .send()
         .await
@@ -103,12 +173,13 @@ async fn set_up_services(mut services: InjectableServices) -> InjectableServices
 
         services = InjectableServices {
             db: services.db,
+            sms_sender: services.sms_sender,
             twilio_address: services.twilio_address,
             winnipeg_transit_api_address: Some("http://localhost:1313".to_string()),
         };
     }
 
-    if services.twilio_address.is_none() {
+    if services.sms_sender.is_none() && services.twilio_address.is_none() {
         let mock_twilio = MockServer::start().await;
 
         Mock::given(any())
@@ -120,6 +191,7 @@ async fn set_up_services(mut services: InjectableServices) -> InjectableServices
 
         services = InjectableServices {
             db: services.db,
+            sms_sender: None,
             twilio_address: Some(mock_twilio.uri()),
             winnipeg_transit_api_address: services.winnipeg_transit_api_address,
         }
@@ -148,3 +220,77 @@ async fn spawn_app(services: InjectableServices) -> TestApp {
 
     TestApp { address }
 }
+
+#[allow(dead_code)]
+pub async fn mock_union_station_stops_and_routes(mock_winnipeg_transit_api: &MockServer) {
+    let mock_locations_response = fs::read_to_string("tests/fixtures/stops/locations.json")
+        .expect("Failed to read locations fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/locations:.*\.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_locations_response))
+        .mount(mock_winnipeg_transit_api)
+        .await;
+
+    let mock_stops_response = fs::read_to_string("tests/fixtures/stops/stops.json")
+        .expect("Failed to read stops fixture");
+    let stops: serde_json::Value =
+        serde_json::from_str(&mock_stops_response).expect("Failed to parse stops fixture as JSON");
+
+    let mut routes_by_stop: HashMap<String, Vec<String>> = HashMap::new();
+
+    for stop in stops["stops"].as_array().unwrap() {
+        let stop_key = stop["key"].as_u64().unwrap().to_string();
+        let mock_routes_response = fs::read_to_string(format!(
+            "tests/fixtures/stops/routes/stop_{}.json",
+            stop_key
+        ))
+        .unwrap_or_else(|_| panic!("Failed to read routes fixture for stop {}", stop_key));
+
+        let routes: serde_json::Value = serde_json::from_str(&mock_routes_response)
+            .expect("Failed to parse routes fixture as JSON");
+
+        routes_by_stop.insert(
+            stop_key.clone(),
+            routes["routes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|route| route["key"].to_string().trim_matches('"').to_lowercase())
+                .collect(),
+        );
+
+        Mock::given(method("GET"))
+            .and(path("/v4/routes.json"))
+            .and(query_param("stop", stop_key.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_string(mock_routes_response))
+            .mount(mock_winnipeg_transit_api)
+            .await;
+    }
+
+    // Like ODWS, only return stops served by the route when one is given
+    Mock::given(method("GET"))
+        .and(path("/v4/stops.json"))
+        .respond_with(move |request: &Request| {
+            let route = request
+                .url
+                .query_pairs()
+                .find(|(key, _)| key == "route")
+                .map(|(_, value)| value.to_lowercase());
+
+            let mut filtered_stops = stops.clone();
+
+            if let Some(route) = route {
+                filtered_stops["stops"]
+                    .as_array_mut()
+                    .unwrap()
+                    .retain(|stop| {
+                        routes_by_stop[&stop["key"].as_u64().unwrap().to_string()].contains(&route)
+                    });
+            }
+
+            ResponseTemplate::new(200).set_body_json(filtered_stops)
+        })
+        .mount(mock_winnipeg_transit_api)
+        .await;
+}
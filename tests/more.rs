@@ -0,0 +1,293 @@
+mod helpers;
+
+use helpers::{get, get_config};
+
+use indoc::indoc;
+use select::{document::Document, predicate::Name};
+use speculoos::prelude::*;
+use sqlx::postgres::PgPool;
+use std::fs;
+use textabus::{
+    conversation::set_overflow, encoding::segment_count, models::Message, InjectableServices,
+};
+use wiremock::matchers::{method, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn more_sends_the_next_page_of_times(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response.clone()),
+        )
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    get(
+        "/twilio?Body=10619&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    let response = get(
+        "/twilio?Body=more&From=approved&To=textabus&MessageSid=SM1850",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+    assert_eq!(response.headers()["content-type"], "text/xml");
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        12:33p 18 Assin Park
+        12:33p BLUE Downtown
+        12:37p 33 Via Mapleglen
+        12:39p 16 Southdale Ctr
+        12:40p 17 Misericordia
+        12:45p 20 Airport
+        12:45p BLUE Downtown
+        (more)"};
+
+    assert_that(body).contains(expected_body);
+
+    let outgoing_message: Message =
+        sqlx::query_as("SELECT * FROM messages ORDER BY created_at DESC LIMIT 1")
+            .fetch_one(&db)
+            .await
+            .expect("Failed to fetch message");
+
+    assert_eq!(outgoing_message.body, expected_body);
+
+    let overflow: Vec<String> =
+        sqlx::query_scalar("SELECT overflow FROM conversation_states WHERE number = 'approved'")
+            .fetch_one(&db)
+            .await
+            .expect("Failed to fetch overflow");
+
+    assert_that(&overflow).does_not_contain("12:39p 16 Southdale Ctr".to_string());
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn more_notes_when_there_is_nothing_more(db: PgPool) {
+    let response = get(
+        "/twilio?Body=more&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("Nothing more to send");
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn more_ignores_expired_pages(db: PgPool) {
+    sqlx::query(
+        "INSERT INTO conversation_states (number, overflow, overflow_expires_at, expires_at, created_at, updated_at)
+        VALUES ('approved', ARRAY['12:25p 60 UofM'], NOW() - INTERVAL '1 minute', NOW() - INTERVAL '1 minute', NOW(), NOW())",
+    )
+    .execute(&db)
+    .await
+    .expect("Failed to set overflow");
+
+    let response = get(
+        "/twilio?Body=more&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("Nothing more to send");
+    assert_that(body).does_not_contain("UofM");
+}
+
+#[sqlx::test(fixtures("numbers-approved", "conversation-stops"))]
+async fn more_ignores_expired_pages_after_a_newer_list(db: PgPool) {
+    // The stops list from conversation-stops came after the pages and is still current
+    sqlx::query(
+        "UPDATE conversation_states
+        SET overflow = ARRAY['12:25p 60 UofM'], overflow_expires_at = NOW() - INTERVAL '1 minute'",
+    )
+    .execute(&db)
+    .await
+    .expect("Failed to set overflow");
+
+    let response = get(
+        "/twilio?Body=more&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("Nothing more to send");
+    assert_that(body).does_not_contain("UofM");
+}
+
+#[sqlx::test(fixtures("numbers-approved", "conversation-stops"))]
+async fn pages_do_not_extend_the_list_expiry(db: PgPool) {
+    sqlx::query("UPDATE conversation_states SET expires_at = NOW() + INTERVAL '1 minute'")
+        .execute(&db)
+        .await
+        .expect("Failed to shorten conversation state");
+
+    set_overflow(&db, "approved", vec!["12:25p 60 UofM".to_string()])
+        .await
+        .expect("Failed to set overflow");
+
+    let expires_in_minutes: f64 = sqlx::query_scalar(
+        "SELECT EXTRACT(EPOCH FROM expires_at - NOW())::FLOAT8 / 60 FROM conversation_states",
+    )
+    .fetch_one(&db)
+    .await
+    .expect("Failed to fetch expiry");
+
+    assert_that(&expires_in_minutes).is_less_than(2.0);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn more_pages_through_help(db: PgPool) {
+    let config = get_config();
+    let mut bodies: Vec<String> = Vec::new();
+
+    for (index, body) in ["help", "more", "more", "more", "more", "more", "more"]
+        .iter()
+        .enumerate()
+    {
+        let response = get(
+            &format!(
+                "/twilio?Body={}&From=approved&To=textabus&MessageSid=SM{}",
+                body, index
+            ),
+            InjectableServices {
+                db: db.clone(),
+                sms_sender: None,
+                twilio_address: None,
+                winnipeg_transit_api_address: None,
+            },
+        )
+        .await
+        .expect("Failed to execute request");
+
+        let document = Document::from(response.text().await.unwrap().as_str());
+        let page = document
+            .find(Name("body"))
+            .next()
+            .unwrap()
+            .text()
+            .trim()
+            .to_string();
+        let is_last_page = !page.contains("(more)");
+
+        bodies.push(page);
+
+        if is_last_page {
+            break;
+        }
+    }
+
+    assert_that(&bodies.len()).is_greater_than(1);
+    assert_that(&bodies[0]).starts_with("textabus commands:\n\nbus times:\n");
+    assert_that(bodies.last().unwrap()).contains("next page of a long response:\nmore\n");
+    assert_that(bodies.last().unwrap()).contains(&config.root_url);
+
+    for page in &bodies {
+        assert_that(&segment_count(page)).is_less_than_or_equal_to(config.response_segment_budget);
+    }
+}
+
+#[sqlx::test(fixtures("numbers-approved", "conversation-stops"))]
+async fn other_commands_clear_pending_pages(db: PgPool) {
+    sqlx::query(
+        "UPDATE conversation_states
+        SET overflow = ARRAY['12:25p 60 UofM'], overflow_expires_at = NOW() + INTERVAL '10 minutes'",
+    )
+        .execute(&db)
+        .await
+        .expect("Failed to set overflow");
+
+    get(
+        "/twilio?Body=settings clock&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    let overflow: Option<Vec<String>> =
+        sqlx::query_scalar("SELECT overflow FROM conversation_states WHERE number = 'approved'")
+            .fetch_one(&db)
+            .await
+            .expect("Failed to fetch overflow");
+
+    assert_eq!(overflow, None);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn more_does_not_work_with_raw_interface(db: PgPool) {
+    let response = get(
+        "/raw?body=more",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let body = response.text().await.unwrap();
+
+    assert_eq!(body, "Cannot get more with this interface");
+}
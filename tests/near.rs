@@ -0,0 +1,235 @@
+mod helpers;
+
+use helpers::{get, mock_union_station_stops_and_routes};
+
+use indoc::indoc;
+use select::{document::Document, predicate::Name};
+use speculoos::prelude::*;
+use sqlx::postgres::PgPool;
+use std::fs;
+use textabus::{
+    conversation::{get_conversation_state, ConversationState},
+    models::ApiResponse,
+    InjectableServices,
+};
+use wiremock::matchers::{method, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn near_returns_route_times_at_closest_stop_in_each_direction(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    mock_union_station_stops_and_routes(&mock_winnipeg_transit_api).await;
+
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(3)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=BLUE near union station&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        blue near Via Rail Station (Union Station) (123 MainSt)
+        12:19p 10625 NB BLUE Downtown (8min late)
+        12:19p 10641 SB BLUE Downtown (8min late)
+        (more)"};
+
+    assert_that(body).contains(expected_body);
+
+    // ODWS filters the stops by route, so no routes are looked up and the closest westbound stop is
+    // never seen
+    let stops_queries: Vec<String> = sqlx::query_as::<_, ApiResponse>(
+        "SELECT * FROM api_responses WHERE query LIKE '/v4/stops.json%' OR query LIKE '/v4/routes.json%'",
+    )
+    .fetch_all(&db)
+    .await
+    .expect("Failed to fetch API responses")
+    .into_iter()
+    .map(|api_response| api_response.query)
+    .collect();
+
+    assert_eq!(stops_queries.len(), 1);
+    assert_that(&stops_queries[0]).contains("route=blue");
+
+    let schedule_queries: Vec<String> = sqlx::query_as::<_, ApiResponse>(
+        "SELECT * FROM api_responses WHERE query LIKE '/v4/stops/%' ORDER BY query",
+    )
+    .fetch_all(&db)
+    .await
+    .expect("Failed to fetch API responses")
+    .into_iter()
+    .map(|api_response| api_response.query)
+    .collect();
+
+    assert_eq!(
+        schedule_queries,
+        vec![
+            "/v4/stops/10157/schedule.json?usage=short",
+            "/v4/stops/10625/schedule.json?usage=short",
+            "/v4/stops/10641/schedule.json?usage=short",
+        ]
+    );
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn near_notes_no_departures_for_route(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    mock_union_station_stops_and_routes(&mock_winnipeg_transit_api).await;
+
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(0)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=99 near union station&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body)
+        .contains("No departures found for 99 near Via Rail Station (Union Station) (123 MainSt)");
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn near_lists_ambiguous_locations(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    let mock_locations_response = fs::read_to_string("tests/fixtures/stops/locations-address.json")
+        .expect("Failed to read locations fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/locations:.*\.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_locations_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops.*$"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=16 near 245 sm&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        Several places match 245 sm, reply with a number or be more specific
+        1. 245 SmithSt
+        2. 245 SmithfieldAve
+        "};
+
+    assert_that(body).contains(expected_body);
+
+    let conversation_state = get_conversation_state(&db, "approved")
+        .await
+        .expect("Failed to fetch conversation state");
+
+    match conversation_state {
+        Some(ConversationState::Locations {
+            routes,
+            locations,
+            near,
+        }) => {
+            assert_eq!(routes, vec!["16"]);
+            assert_eq!(locations.len(), 2);
+            assert!(near);
+        }
+        _ => panic!("Expected a locations conversation state"),
+    }
+}
+
+#[sqlx::test(fixtures("numbers-approved", "conversation-near-locations"))]
+async fn near_location_choice_returns_route_times_near_that_place(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    mock_union_station_stops_and_routes(&mock_winnipeg_transit_api).await;
+
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(3)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=2&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        blue near Via Rail Station (Union Station) (123 MainSt)
+        12:19p 10625 NB BLUE Downtown (8min late)
+        12:19p 10641 SB BLUE Downtown (8min late)
+        (more)"};
+
+    assert_that(body).contains(expected_body);
+}
@@ -0,0 +1,144 @@
+mod helpers;
+
+use helpers::{get, get_config};
+
+use select::{document::Document, predicate::Name};
+use speculoos::prelude::*;
+use sqlx::postgres::PgPool;
+use std::sync::Arc;
+use textabus::{
+    models::{Message, Number},
+    sms::{RecordedSms, RecordingSmsSender},
+    InjectableServices,
+};
+
+async fn text(body: &str, from: &str, db: &PgPool) -> reqwest::Response {
+    get(
+        &format!(
+            "/twilio?Body={}&From={}&To=textabus&MessageSid=SM1849",
+            body, from
+        ),
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request")
+}
+
+async fn fetch_number(number: &str, db: &PgPool) -> Number {
+    sqlx::query_as("SELECT * FROM numbers WHERE number = $1")
+        .bind(number)
+        .fetch_one(db)
+        .await
+        .expect("Failed to fetch number")
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn stop_opts_out_and_confirms(db: PgPool) {
+    let response = text("STOP", "approved", &db).await;
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("unsubscribed from textabus");
+    assert!(fetch_number("approved", &db).await.opted_out);
+}
+
+#[sqlx::test(fixtures("numbers-approved", "numbers-opted-out"))]
+async fn start_opts_back_in(db: PgPool) {
+    let response = text("Unstop", "approved", &db).await;
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("resubscribed to textabus");
+    assert!(!fetch_number("approved", &db).await.opted_out);
+}
+
+#[sqlx::test(fixtures("numbers-approved", "numbers-opted-out"))]
+async fn opted_out_number_gets_no_reply(db: PgPool) {
+    let response = text("10619", "approved", &db).await;
+
+    assert!(response.status().is_success());
+    assert_eq!(response.text().await.unwrap(), "<Response></Response>");
+
+    let [incoming_message]: [Message; 1] = sqlx::query_as("SELECT * FROM messages")
+        .fetch_all(&db)
+        .await
+        .expect("Failed to fetch messages")
+        .try_into()
+        .expect("Expected exactly 1 message");
+
+    assert_eq!(incoming_message.body, "10619");
+}
+
+#[sqlx::test]
+async fn stop_from_unknown_number_records_it_without_a_welcome(db: PgPool) {
+    let response = text("cancel", "unknown", &db).await;
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("unsubscribed from textabus");
+
+    let number = fetch_number("unknown", &db).await;
+
+    assert!(number.opted_out);
+    assert!(!number.approved);
+}
+
+#[sqlx::test]
+async fn start_from_unknown_number_gets_the_welcome(db: PgPool) {
+    let sms_sender = Arc::new(RecordingSmsSender::default());
+
+    let response = get(
+        "/twilio?Body=START&From=unknown&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: Some(sms_sender.clone()),
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("welcome to textabus");
+    assert_that(body).does_not_contain("resubscribed");
+
+    let config = get_config();
+
+    assert_eq!(
+        *sms_sender.sent.lock().unwrap(),
+        vec![RecordedSms {
+            from: config.textabus_number.clone(),
+            to: config.admin_number.clone(),
+            body: "New number: unknown".to_string(),
+        }]
+    );
+
+    let number = fetch_number("unknown", &db).await;
+
+    assert!(!number.opted_out);
+    assert!(!number.approved);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn start_from_subscribed_number_is_handled_like_any_other_text(db: PgPool) {
+    let response = text("yes", "approved", &db).await;
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("textabus commands:");
+    assert_that(body).does_not_contain("resubscribed");
+}
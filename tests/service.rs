@@ -0,0 +1,196 @@
+mod helpers;
+
+use helpers::get;
+
+use chrono::{NaiveDateTime, Utc};
+use chrono_tz::America::Winnipeg;
+use indoc::indoc;
+use select::{document::Document, predicate::Name};
+use speculoos::prelude::*;
+use sqlx::postgres::PgPool;
+use std::fs;
+use textabus::{models::ApiResponse, InjectableServices};
+use wiremock::matchers::{method, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn last_returns_final_departure_per_route(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=last 10619 16 60&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        Last buses at 10619 WB Graham@Vaughan (The Bay)
+        2:00p 60 UofM
+        2:03p 16 Southdale Ctr
+        "};
+
+    assert_that(body).contains(expected_body);
+
+    let api_response: ApiResponse = sqlx::query_as("SELECT * FROM api_responses LIMIT 1")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to fetch API response");
+
+    assert_that(&api_response.query)
+        .starts_with("/v4/stops/10619/schedule.json?usage=short&start=");
+    assert_that(&api_response.query).contains("&end=");
+
+    // The window starts at Winnipeg’s time, not the server’s
+    let start = NaiveDateTime::parse_from_str(
+        &api_response.query["/v4/stops/10619/schedule.json?usage=short&start=".len()..][..19],
+        "%Y-%m-%dT%H:%M:%S",
+    )
+    .expect("Failed to parse window start");
+    let winnipeg_now = Utc::now().with_timezone(&Winnipeg).naive_local();
+
+    assert_that(&(winnipeg_now - start).num_minutes().abs()).is_less_than(2);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn first_returns_first_departure_per_route(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=first 10619 16 blue&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        First buses at 10619 WB Graham@Vaughan (The Bay)
+        12:16p 16 St Vital Ctr (1min ahead)
+        12:19p BLUE Downtown (8min late)
+        "};
+
+    assert_that(body).contains(expected_body);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn last_skips_cancelled_trips(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mut stop_schedule: serde_json::Value = serde_json::from_str(
+        &fs::read_to_string("tests/fixtures/times/stop_schedule_statuses.json")
+            .expect("Failed to read stop schedule fixture"),
+    )
+    .expect("Failed to parse stop schedule fixture as JSON");
+
+    // The day’s last 16 to Southdale is cancelled too
+    let last_trip = stop_schedule["stop-schedule"]["route-schedules"]
+        .as_array_mut()
+        .unwrap()
+        .iter_mut()
+        .flat_map(|route_schedule| route_schedule["scheduled-stops"].as_array_mut().unwrap())
+        .find(|scheduled_stop| scheduled_stop["key"] == "25594569-51")
+        .expect("Failed to find the last trip");
+    last_trip["cancelled"] = "true".into();
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(stop_schedule))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=last 10619 16&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        Last buses at 10619 WB Graham@Vaughan (The Bay)
+        1:42p 16 St Vital Ctr
+        "};
+
+    assert_that(body).contains(expected_body);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn last_notes_no_more_departures(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=last 10619 99&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body)
+        .contains("No more departures today for 99 at 10619 WB Graham@Vaughan (The Bay)");
+}
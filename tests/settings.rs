@@ -6,7 +6,7 @@ use select::{document::Document, predicate::Name};
 use speculoos::prelude::*;
 use sqlx::postgres::PgPool;
 use textabus::{
-    models::{Message, Number},
+    models::{Message, Number, TimesFormat},
     InjectableServices,
 };
 
@@ -16,6 +16,7 @@ async fn settings_clock_toggles_off_twelve_hour_field(db: PgPool) {
         "/twilio?Body=settings clock&From=approved&To=textabus&MessageSid=SM1849",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: None,
         },
@@ -70,6 +71,7 @@ async fn settings_clock_toggles_on_twelve_hour_field(db: PgPool) {
         "/twilio?Body=settings clock&From=approved&To=textabus&MessageSid=SM1849",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: None,
         },
@@ -124,6 +126,7 @@ async fn settings_clock_does_not_work_with_raw_interface(db: PgPool) {
         "/raw?body=settings clock",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: None,
         },
@@ -164,3 +167,127 @@ async fn settings_clock_does_not_work_with_raw_interface(db: PgPool) {
         Some(incoming_message.id)
     );
 }
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn settings_format_sets_times_format(db: PgPool) {
+    let response = get(
+        "/twilio?Body=settings format grouped&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("times will now be grouped by route");
+
+    let [number]: [Number; 1] = sqlx::query_as("SELECT * FROM numbers")
+        .fetch_all(&db)
+        .await
+        .expect("Failed to fetch numbers")
+        .try_into()
+        .expect("Expected exactly 1 number");
+
+    assert_eq!(number.times_format, TimesFormat::Grouped);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn settings_countdown_sets_countdown_horizon(db: PgPool) {
+    let response = get(
+        "/twilio?Body=settings countdown 15&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("times within 15min will now be a countdown");
+
+    let [number]: [Number; 1] = sqlx::query_as("SELECT * FROM numbers")
+        .fetch_all(&db)
+        .await
+        .expect("Failed to fetch numbers")
+        .try_into()
+        .expect("Expected exactly 1 number");
+
+    assert_eq!(number.countdown_horizon, Some(15));
+}
+
+#[sqlx::test(fixtures("numbers-approved", "numbers-countdown"))]
+async fn settings_countdown_off_clears_countdown_horizon(db: PgPool) {
+    let response = get(
+        "/twilio?Body=settings countdown off&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("times will now always be clock times");
+
+    let [number]: [Number; 1] = sqlx::query_as("SELECT * FROM numbers")
+        .fetch_all(&db)
+        .await
+        .expect("Failed to fetch numbers")
+        .try_into()
+        .expect("Expected exactly 1 number");
+
+    assert_eq!(number.countdown_horizon, None);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn settings_accents_sets_accents(db: PgPool) {
+    let response = get(
+        "/twilio?Body=settings accents on&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("replies will now keep accents and symbols");
+
+    let [number]: [Number; 1] = sqlx::query_as("SELECT * FROM numbers")
+        .fetch_all(&db)
+        .await
+        .expect("Failed to fetch numbers")
+        .try_into()
+        .expect("Expected exactly 1 number");
+
+    assert!(number.accents);
+}
@@ -0,0 +1,106 @@
+mod helpers;
+
+use helpers::{get_with_signature, post_form, sign, sign_form};
+
+use sqlx::postgres::PgPool;
+use textabus::InjectableServices;
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn twilio_accepts_a_signed_request(db: PgPool) {
+    let path = "/twilio?Body=help&From=approved&To=textabus&MessageSid=SM1849";
+    let signature = sign(path);
+
+    let response = get_with_signature(
+        path,
+        Some(&signature),
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn twilio_rejects_an_unsigned_request(db: PgPool) {
+    let response = get_with_signature(
+        "/twilio?Body=help&From=approved&To=textabus&MessageSid=SM1849",
+        None,
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert_eq!(response.status(), 403);
+
+    let message_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to fetch message count");
+
+    assert_eq!(message_count, 0);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn twilio_rejects_a_signature_for_different_parameters(db: PgPool) {
+    let signature = sign("/twilio?Body=help&From=someone&To=textabus&MessageSid=SM1849");
+
+    let response = get_with_signature(
+        "/twilio?Body=help&From=approved&To=textabus&MessageSid=SM1849",
+        Some(&signature),
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert_eq!(response.status(), 403);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn twilio_rejects_a_post_signed_for_different_parameters(db: PgPool) {
+    let signature = sign_form(
+        "/twilio",
+        &[
+            ("Body", "help"),
+            ("From", "someone"),
+            ("To", "textabus"),
+            ("MessageSid", "SM1849"),
+        ],
+    );
+
+    let response = post_form(
+        "/twilio",
+        &[
+            ("Body", "help"),
+            ("From", "approved"),
+            ("To", "textabus"),
+            ("MessageSid", "SM1849"),
+        ],
+        Some(&signature),
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert_eq!(response.status(), 403);
+}
@@ -1,6 +1,6 @@
 mod helpers;
 
-use helpers::get;
+use helpers::{get, mock_union_station_stops_and_routes};
 
 use assertables::assert_starts_with;
 use indoc::indoc;
@@ -9,6 +9,7 @@ use speculoos::prelude::*;
 use sqlx::postgres::PgPool;
 use std::fs;
 use textabus::{
+    conversation::{get_conversation_state, ConversationState},
     models::{ApiResponse, Message},
     InjectableServices,
 };
@@ -80,6 +81,7 @@ async fn stops_returns_stops_and_routes_near_a_location(db: PgPool) {
         "/twilio?Body=Stops Union Station&From=approved&To=textabus&MessageSid=SM1849",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
         },
@@ -98,27 +100,8 @@ async fn stops_returns_stops_and_routes_near_a_location(db: PgPool) {
 
     let expected_body = indoc! {"
         Stops near Via Rail Station (Union Station) (123 MainSt)
-
-        10625 NB Main@Broadway (Union Station) BLUE 14 19 47 53 54 55 57 59 68
-
-        10641 SB Main@Broadway (Union Station) BLUE 14 19 23 47 53 54 55 57 59 65 66 68
-
-        11052 WB Broadway@Main 34 65 66
-
-        11010 NB Fort@Broadway 34
-
-        10901 SB Israel Asper@Canadian Museum for Human Rights 38
-
-        10902 NB Israel Asper@Canadian Museum for Human Rights 38
-
-        10624 NB Main@Assiniboine BLUE 14 19 47 53 54 55 57 59 68
-
-        10830 NB Fort@Assiniboine 23
-
-        10907 EB Forks Market@The Forks Market 38
-
-        10639 SB Main@St. Mary BLUE 14 19 34 47 53 54 55 57 59 68
-    "};
+        1. 10625 NB Main@Broadway (Union Station) 56m BLUE 14 19 47 53 54 55 57 59 68
+        (more)"};
 
     assert_that(body).contains(expected_body);
 
@@ -134,6 +117,19 @@ async fn stops_returns_stops_and_routes_near_a_location(db: PgPool) {
 
     assert_eq!(outgoing_message.body, expected_body);
 
+    let conversation_state = get_conversation_state(&db, "approved")
+        .await
+        .expect("Failed to fetch conversation state");
+
+    assert_eq!(
+        conversation_state,
+        Some(ConversationState::Stops {
+            stop_numbers: vec![
+                10625, 10641, 11052, 10907, 11010, 10901, 10902, 10624, 10830, 10639
+            ]
+        })
+    );
+
     let api_responses: Vec<ApiResponse> = sqlx::query_as("SELECT * FROM api_responses")
         .fetch_all(&db)
         .await
@@ -158,7 +154,7 @@ async fn stops_returns_stops_and_routes_near_a_location(db: PgPool) {
     assert_eq!(stops_response.body, mock_stops_response);
     assert_starts_with!(
         stops_response.query,
-        format!("/v4/stops.json?lat=49.88895&lon=-97.13424&distance=500&usage=short")
+        format!("/v4/stops.json?lat=49.88895&lon=-97.13424&distance=500&walking=true&usage=short")
     );
 
     let routes_responses: Vec<&ApiResponse> = api_responses.iter().skip(2).collect();
@@ -196,6 +192,7 @@ async fn stops_handles_an_empty_locations_response(db: PgPool) {
         "/twilio?Body=stops acab&From=approved&To=textabus&MessageSid=SM1849",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
         },
@@ -259,6 +256,7 @@ async fn stops_handles_an_empty_stops_response(db: PgPool) {
         "/twilio?Body=stops assiniboia downs&From=approved&To=textabus&MessageSid=SM1849",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
         },
@@ -288,3 +286,564 @@ async fn stops_handles_an_empty_stops_response(db: PgPool) {
 
     assert_eq!(api_responses_record_count, 2);
 }
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn stops_filters_by_route(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    mock_union_station_stops_and_routes(&mock_winnipeg_transit_api).await;
+
+    let response = get(
+        "/twilio?Body=stops union station on 38&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        Stops near Via Rail Station (Union Station) (123 MainSt) for 38 within 500m
+        1. 10901 SB Israel Asper@Canadian Museum for Human Rights 190m 38
+        (more)"};
+
+    assert_that(body).contains(expected_body);
+
+    let conversation_state = get_conversation_state(&db, "approved")
+        .await
+        .expect("Failed to fetch conversation state");
+
+    assert_eq!(
+        conversation_state,
+        Some(ConversationState::Stops {
+            stop_numbers: vec![10901, 10902, 10907, 10804, 10939, 10803]
+        })
+    );
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn stops_keeps_a_trailing_number_in_the_location(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    mock_union_station_stops_and_routes(&mock_winnipeg_transit_api).await;
+
+    let response = get(
+        "/twilio?Body=stops highway 59&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let queries: Vec<String> = sqlx::query_scalar(
+        "SELECT query FROM api_responses WHERE query LIKE '/v4/locations:%' OR query LIKE '/v4/stops.json%' ORDER BY created_at",
+    )
+    .fetch_all(&db)
+    .await
+    .expect("Failed to fetch API responses");
+
+    assert_starts_with!(queries[0], "/v4/locations:highway 59.json");
+    assert!(queries[1..].iter().all(|query| !query.contains("route=")));
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn stops_with_route_filter_widens_the_search(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    let mock_no_stops_response = fs::read_to_string("tests/fixtures/stops/stops-none.json")
+        .expect("Failed to read stops fixture");
+
+    Mock::given(method("GET"))
+        .and(path("/v4/stops.json"))
+        .and(query_param("route", "43"))
+        .and(query_param("distance", "500"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_no_stops_response))
+        .with_priority(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    mock_union_station_stops_and_routes(&mock_winnipeg_transit_api).await;
+
+    let response = get(
+        "/twilio?Body=stops union station on 43&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        Stops near Via Rail Station (Union Station) (123 MainSt) for 43 within 1000m
+        1. 10803 EB William Stephenson@Canadian Museum for Human Rights 396m 10 38 43 49 50 56
+        (more)"};
+
+    assert_that(body).contains(expected_body);
+
+    let stops_queries: Vec<String> = sqlx::query_as::<_, ApiResponse>(
+        "SELECT * FROM api_responses WHERE query LIKE '/v4/stops.json%' ORDER BY created_at",
+    )
+    .fetch_all(&db)
+    .await
+    .expect("Failed to fetch API responses")
+    .into_iter()
+    .map(|api_response| api_response.query)
+    .collect();
+
+    assert_eq!(stops_queries.len(), 2);
+    assert_that(&stops_queries[0]).contains("&distance=500&");
+    assert_that(&stops_queries[0]).ends_with("&route=43");
+    assert_that(&stops_queries[1]).contains("&distance=1000&");
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn stops_with_route_filter_notes_none_found(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    mock_union_station_stops_and_routes(&mock_winnipeg_transit_api).await;
+
+    let response = get(
+        "/twilio?Body=stops union station on 99&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains(
+        "No stops for 99 found within 2000m of Via Rail Station (Union Station) (123 MainSt)",
+    );
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn stops_accepts_coordinates_without_a_locations_lookup(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/locations:.*\.json$"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    mock_union_station_stops_and_routes(&mock_winnipeg_transit_api).await;
+
+    let response = get(
+        "/twilio?Body=stops geo:49.88895,-97.13424&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        Stops near 49.88895,-97.13424
+        1. 10625 NB Main@Broadway (Union Station) 56m BLUE 14 19 47 53 54 55 57 59 68
+        (more)"};
+
+    assert_that(body).contains(expected_body);
+
+    let stops_query: String = sqlx::query_scalar(
+        "SELECT query FROM api_responses WHERE query LIKE '/v4/stops.json%' LIMIT 1",
+    )
+    .fetch_one(&db)
+    .await
+    .expect("Failed to fetch API response");
+
+    assert_starts_with!(stops_query, "/v4/stops.json?lat=49.88895&lon=-97.13424&");
+}
+
+#[sqlx::test(fixtures("numbers-approved", "aliases"))]
+async fn stops_uses_alias_coordinates_without_a_locations_lookup(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/locations:.*\.json$"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    mock_union_station_stops_and_routes(&mock_winnipeg_transit_api).await;
+
+    let response = get(
+        "/twilio?Body=stops The Forks&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        Stops near the forks
+        1. 10625 NB Main@Broadway (Union Station) 56m BLUE 14 19 47 53 54 55 57 59 68"};
+
+    assert_that(body).contains(expected_body);
+}
+
+#[sqlx::test(fixtures("numbers-approved", "aliases"))]
+async fn stops_searches_for_alias_query(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    mock_union_station_stops_and_routes(&mock_winnipeg_transit_api).await;
+
+    let response = get(
+        "/twilio?Body=stops train station&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("Stops near Via Rail Station (Union Station) (123 MainSt)");
+
+    let locations_query: String = sqlx::query_scalar(
+        "SELECT query FROM api_responses WHERE query LIKE '/v4/locations%' LIMIT 1",
+    )
+    .fetch_one(&db)
+    .await
+    .expect("Failed to fetch API response");
+
+    assert_starts_with!(locations_query, "/v4/locations:Union Station.json?");
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn stops_notes_an_unreadable_link_without_a_locations_lookup(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/locations:.*\.json$"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=stops https://maps.app.goo.gl/Z9xTzEx1&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("Couldn't read a location from that link");
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn stops_lists_ambiguous_locations(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    let mock_locations_response = fs::read_to_string("tests/fixtures/stops/locations-address.json")
+        .expect("Failed to read locations fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/locations:.*\.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_locations_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops.json$"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=stops 245 sm on 16&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        Several places match 245 sm, reply with a number or be more specific
+        1. 245 SmithSt
+        2. 245 SmithfieldAve
+        "};
+
+    assert_that(body).contains(expected_body);
+
+    let conversation_state = get_conversation_state(&db, "approved")
+        .await
+        .expect("Failed to fetch conversation state");
+
+    match conversation_state {
+        Some(ConversationState::Locations {
+            routes,
+            locations,
+            near,
+        }) => {
+            assert_eq!(routes, vec!["16"]);
+            assert!(!near);
+            assert_eq!(locations.len(), 2);
+            assert_eq!(locations[1].name, "245 SmithfieldAve");
+            assert_eq!(locations[1].latitude.to_string(), "49.9319");
+        }
+        _ => panic!("Expected a locations conversation state"),
+    }
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn stops_uses_the_location_named_in_the_query(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    let mock_locations_response = fs::read_to_string("tests/fixtures/stops/locations-address.json")
+        .expect("Failed to read locations fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/locations:.*\.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_locations_response))
+        .with_priority(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    mock_union_station_stops_and_routes(&mock_winnipeg_transit_api).await;
+
+    let response = get(
+        "/twilio?Body=stops 245 smith&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("Stops near 245 SmithSt\n1. 10625");
+}
+
+#[sqlx::test(fixtures("numbers-approved", "conversation-locations"))]
+async fn stops_location_choice_returns_stops_near_that_place(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    mock_union_station_stops_and_routes(&mock_winnipeg_transit_api).await;
+
+    let response = get(
+        "/twilio?Body=2&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        Stops near Via Rail Station (Union Station) (123 MainSt)
+        1. 10625 NB Main@Broadway (Union Station) 56m BLUE 14 19 47 53 54 55 57 59 68
+        (more)"};
+
+    assert_that(body).contains(expected_body);
+
+    let stops_query: String = sqlx::query_scalar(
+        "SELECT query FROM api_responses WHERE query LIKE '/v4/stops.json%' LIMIT 1",
+    )
+    .fetch_one(&db)
+    .await
+    .expect("Failed to fetch API response");
+
+    assert_starts_with!(stops_query, "/v4/stops.json?lat=49.88895&lon=-97.13424&");
+
+    let conversation_state = get_conversation_state(&db, "approved")
+        .await
+        .expect("Failed to fetch conversation state");
+
+    assert!(matches!(
+        conversation_state,
+        Some(ConversationState::Stops { .. })
+    ));
+}
+
+#[sqlx::test(fixtures("numbers-approved", "conversation-locations"))]
+async fn stops_location_choice_outside_the_list_is_noted(db: PgPool) {
+    let response = get(
+        "/twilio?Body=3&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("No place 3 in the last list, choose 1 to 2");
+}
+
+#[sqlx::test(fixtures("numbers-approved", "conversation-stops"))]
+async fn stops_list_number_returns_times_for_that_stop(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path("/v4/stops/10619/schedule.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=2&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains(indoc! {"
+        10619 WB Graham@Vaughan (The Bay)
+        12:16p 16 St Vital Ctr (1min ahead)
+    "});
+}
+
+#[sqlx::test(fixtures("numbers-approved", "conversation-stops"))]
+async fn stops_list_number_outside_the_list_is_noted(db: PgPool) {
+    let response = get(
+        "/twilio?Body=3&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("No stop 3 in the last stops list, choose 1 to 2");
+}
+
+#[sqlx::test(fixtures("numbers-approved", "conversation-stops"))]
+async fn stops_list_number_is_ignored_after_expiry(db: PgPool) {
+    sqlx::query("UPDATE conversation_states SET expires_at = NOW() - INTERVAL '1 minute'")
+        .execute(&db)
+        .await
+        .expect("Failed to expire conversation state");
+
+    let response = get(
+        "/twilio?Body=1&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("No recent list to choose from, try stops [location]");
+}
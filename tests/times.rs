@@ -11,7 +11,7 @@ use textabus::{
     models::{ApiResponse, Message},
     InjectableServices,
 };
-use wiremock::matchers::{method, path_regex};
+use wiremock::matchers::{method, path, path_regex, query_param};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 #[sqlx::test(fixtures("numbers-approved"))]
@@ -33,6 +33,7 @@ async fn stop_number_returns_stop_schedule(db: PgPool) {
         "/twilio?Body=10619&From=approved&To=textabus&MessageSid=SM1849",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
         },
@@ -52,7 +53,7 @@ async fn stop_number_returns_stop_schedule(db: PgPool) {
         12:19p BLUE Downtown (8min late)
         12:22p BLUE Downtown
         12:25p 60 UofM
-        "};
+        (more)"};
 
     assert_that(body).contains(expected_body);
 
@@ -109,6 +110,7 @@ async fn stop_number_returns_stop_schedule_in_24h_clock_when_number_prefers(db:
         "/twilio?Body=10619&From=approved&To=textabus&MessageSid=SM1849",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
         },
@@ -128,7 +130,7 @@ async fn stop_number_returns_stop_schedule_in_24h_clock_when_number_prefers(db:
         12:19 BLUE Downtown (8min late)
         12:22 BLUE Downtown
         12:25 60 UofM
-        "};
+        (more)"};
 
     assert_that(body).contains(expected_body);
 }
@@ -152,6 +154,7 @@ async fn stop_number_returns_single_route_stop_schedule_to_approved_number(db: P
         "/twilio?Body= 10619 16 18 60&From=approved&To=textabus&MessageSid=SM1849",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
         },
@@ -171,7 +174,8 @@ async fn stop_number_returns_single_route_stop_schedule_to_approved_number(db: P
         12:25p 60 UofM
         12:33p 18 Assin Park
         12:39p 16 Southdale Ctr
-        "};
+        12:56p 18 Assin Park
+        (more)"};
 
     assert_that(body).contains(expected_body);
 
@@ -217,6 +221,7 @@ async fn stop_number_returns_single_route_ignoring_case(db: PgPool) {
         "/twilio?Body=10619 blue&From=approved&To=textabus&MessageSid=SM1849",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
         },
@@ -232,10 +237,7 @@ async fn stop_number_returns_single_route_ignoring_case(db: PgPool) {
 
     let expected_body = indoc! {"
         10619 WB Graham@Vaughan (The Bay)
-        12:19p BLUE Downtown (8min late)
-        12:22p BLUE Downtown
-        12:33p BLUE Downtown
-        12:45p BLUE Downtown
+        BLUE Downtown: 12:19p (8min late) 12:22p 12:33p 12:45p 12:56p 1:08p 1:19p 1:31p 1:42p 1:54p 2:05p
         "};
 
     assert_that(body).contains(expected_body);
@@ -260,6 +262,7 @@ async fn stop_number_notes_no_matching_routes(db: PgPool) {
         "/twilio?Body=10619 99&From=approved&To=textabus&MessageSid=SM1849",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
         },
@@ -296,6 +299,7 @@ async fn incorrect_stop_number_returns_error(db: PgPool) {
         "/twilio?Body= 10619 16 18 60&From=approved&To=textabus&MessageSid=SM1849",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
         },
@@ -355,6 +359,7 @@ async fn stop_number_returns_stop_schedule_via_raw_endpoint(db: PgPool) {
         "/raw?body=10619",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
         },
@@ -376,7 +381,7 @@ async fn stop_number_returns_stop_schedule_via_raw_endpoint(db: PgPool) {
         12:19p BLUE Downtown (8min late)
         12:22p BLUE Downtown
         12:25p 60 UofM
-        "};
+        (more via SMS)"};
 
     assert_eq!(body, expected_body);
 
@@ -434,6 +439,7 @@ async fn stop_number_returns_stop_schedule_issue_10(db: PgPool) {
         "/twilio?Body= 10620&From=approved&To=textabus&MessageSid=SM1849",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
         },
@@ -453,6 +459,438 @@ async fn stop_number_returns_stop_schedule_issue_10(db: PgPool) {
         12:58a 55 UofW via Dakota (4min late)
         1:10a 19 Via Logan
         1:11a 14 Ferry Rd
+        (more)"};
+
+    assert_that(body).contains(expected_body);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn multiple_stop_numbers_return_merged_schedules(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    for (stop_number, fixture) in [
+        ("10619", "tests/fixtures/times/stop_schedule.json"),
+        ("10620", "tests/fixtures/times/stop_schedule_issue_10.json"),
+    ] {
+        let mock_stop_schedule_response =
+            fs::read_to_string(fixture).expect("Failed to read stop schedule fixture");
+
+        Mock::given(method("GET"))
+            .and(path(format!("/v4/stops/{}/schedule.json", stop_number)))
+            .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+            .expect(1)
+            .mount(&mock_winnipeg_transit_api)
+            .await;
+    }
+
+    let response = get(
+        "/twilio?Body=10619 10620 60 55&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        10619 WB Graham@Vaughan (The Bay)
+        10620 WB St Mary@Fort
+        12:25p 10619 60 UofM
+        12:57p 10619 60 UofM
+        1:28p 10619 60 UofM
+        2:00p 10619 60 UofM
+        (more)"};
+
+    assert_that(body).contains(expected_body);
+
+    let api_responses_record_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM api_responses")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to fetch api_responses count");
+
+    assert_eq!(api_responses_record_count, 2);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn too_many_stop_numbers_are_refused_without_api_calls(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=10619 10620 10621 10622 10623&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("Times can show up to 4 stops at once, please send fewer");
+
+    let api_responses_record_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM api_responses")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to fetch api_responses count");
+
+    assert_eq!(api_responses_record_count, 0);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn stop_number_filters_by_destination(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=10619 16 to southdale&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        10619 WB Graham@Vaughan (The Bay)
+        12:39p 16 Southdale Ctr
+        1:21p 16 Southdale Ctr
+        2:03p 16 Southdale Ctr
+        "};
+
+    assert_that(body).contains(expected_body);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn stop_number_filters_by_each_variants_direction(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    // Both 16 variants serve 10619, one leaving it southward and the other northward
+    let variant_path = |next_latitude: f64| {
+        serde_json::json!({
+            "stops": [
+                {
+                    "number": 10619,
+                    "centre": {"geographic": {"latitude": 49.89071, "longitude": -97.149}}
+                },
+                {
+                    "number": 10620,
+                    "centre": {"geographic": {"latitude": next_latitude, "longitude": -97.149}}
+                }
+            ]
+        })
+    };
+
+    Mock::given(method("GET"))
+        .and(path("/v4/stops.json"))
+        .and(query_param("variant", "16-1-V"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(variant_path(49.88)))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    Mock::given(method("GET"))
+        .and(path("/v4/stops.json"))
+        .and(query_param("variant", "16-1-##"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(variant_path(49.9)))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=10619 16 south&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("16 St Vital Ctr");
+    assert_that(body).does_not_contain("Southdale");
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn stop_number_notes_no_departures_in_direction(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=10619 16 north&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        10619 WB Graham@Vaughan (The Bay)
+        No departures found matching 16 north"};
+
+    assert_that(body).contains(expected_body);
+}
+
+#[sqlx::test(fixtures("numbers-approved", "numbers-grouped"))]
+async fn stop_number_returns_grouped_times_when_number_prefers(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=10619 16 to southdale&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        10619 WB Graham@Vaughan (The Bay)
+        16 Southdale Ctr: 12:39p 1:21p 2:03p
+        "};
+
+    assert_that(body).contains(expected_body);
+}
+
+#[sqlx::test(fixtures("numbers-approved", "numbers-headway"))]
+async fn stop_number_returns_headway_summary_when_number_prefers(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=10619 blue 18&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        10619 WB Graham@Vaughan (The Bay)
+        BLUE Downtown every 11-12 min until 2:05p, 12:19p (8min late)
+        18 Assin Park: 12:33p 12:56p 1:18p 1:41p 2:03p
+        "};
+
+    assert_that(body).contains(expected_body);
+}
+
+#[sqlx::test(fixtures("numbers-approved", "numbers-headway", "numbers-accents"))]
+async fn stop_number_keeps_symbols_when_number_prefers_accents(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=10619 blue 18&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    // The dash makes the reply UCS-2, so less fits on the page
+    let expected_body = indoc! {"
+        10619 WB Graham@Vaughan (The Bay)
+        BLUE Downtown every 11–12 min until 2:05p, 12:19p (8min late)
+        (more)"};
+
+    assert_that(body).contains(expected_body);
+}
+
+#[sqlx::test(fixtures("numbers-approved", "numbers-countdown"))]
+async fn stop_number_returns_countdown_times_when_number_prefers(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=10619 16 60&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    let expected_body = indoc! {"
+        10619 WB Graham@Vaughan (The Bay)
+        16 St Vital Ctr: now (1min ahead) 1:00p 1:42p
+        60 UofM: 8min 12:57p 1:28p 2:00p
+        16 Southdale Ctr: 12:39p 1:21p 2:03p
+        "};
+
+    assert_that(body).contains(expected_body);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn stop_number_marks_cancelled_scheduled_only_and_amenities(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response =
+        fs::read_to_string("tests/fixtures/times/stop_schedule_statuses.json")
+            .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let response = get(
+        "/twilio?Body=10619 16&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    // Markers sent as words keep the response in GSM-7, so it fits grouped on one page
+    let expected_body = indoc! {"
+        10619 WB Graham@Vaughan (The Bay)
+        16 St Vital Ctr: 12:16p (1min ahead) bike access ~1:00p 1:42p
+        16 Southdale Ctr: 12:39p cancelled 1:21p 2:03p
         "};
 
     assert_that(body).contains(expected_body);
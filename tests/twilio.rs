@@ -0,0 +1,323 @@
+mod helpers;
+
+use helpers::{get, post_form, sign_form};
+
+use select::{document::Document, predicate::Name};
+use speculoos::prelude::*;
+use sqlx::postgres::PgPool;
+use std::fs;
+use textabus::{models::Message, InjectableServices};
+use uuid::Uuid;
+use wiremock::matchers::{method, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn twilio_accepts_a_posted_form_and_stores_its_details(db: PgPool) {
+    let params = [
+        ("ToCountry", "CA"),
+        ("ToState", "MB"),
+        ("SmsMessageSid", "SM1849"),
+        ("NumMedia", "0"),
+        ("ToCity", "WINNIPEG"),
+        ("FromZip", ""),
+        ("SmsSid", "SM1849"),
+        ("FromState", "MB"),
+        ("SmsStatus", "received"),
+        ("FromCity", "WINNIPEG"),
+        ("Body", "help"),
+        ("FromCountry", "CA"),
+        ("To", "textabus"),
+        ("ToZip", ""),
+        ("NumSegments", "1"),
+        ("MessageSid", "SM1849"),
+        ("AccountSid", "FAKE"),
+        ("From", "approved"),
+        ("ApiVersion", "2010-04-01"),
+    ];
+
+    let signature = sign_form("/twilio", &params);
+
+    let response = post_form(
+        "/twilio",
+        &params,
+        Some(&signature),
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+    assert_eq!(response.headers()["content-type"], "text/xml");
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let body = &document.find(Name("body")).next().unwrap().text();
+
+    assert_that(body).contains("textabus commands:\n\nbus times:\n");
+    assert_that(body).contains("(more)");
+
+    let [incoming_message, outgoing_message]: [Message; 2] =
+        sqlx::query_as("SELECT * FROM messages ORDER BY created_at")
+            .fetch_all(&db)
+            .await
+            .expect("Failed to fetch messages")
+            .try_into()
+            .expect("Expected exactly 2 messages");
+
+    assert_eq!(incoming_message.message_sid, Some("SM1849".to_string()));
+    assert_eq!(incoming_message.body, "help");
+    assert_eq!(incoming_message.num_media, Some(0));
+    assert_eq!(incoming_message.from_city, Some("WINNIPEG".to_string()));
+    assert_eq!(incoming_message.sms_status, Some("received".to_string()));
+
+    assert_eq!(
+        outgoing_message.initial_message_id,
+        Some(incoming_message.id)
+    );
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn twilio_stores_an_empty_from_city_as_none(db: PgPool) {
+    let params = [
+        ("Body", "help"),
+        ("From", "approved"),
+        ("To", "textabus"),
+        ("MessageSid", "SM1849"),
+        ("FromCity", ""),
+        ("NumMedia", "1"),
+    ];
+
+    let signature = sign_form("/twilio", &params);
+
+    let response = post_form(
+        "/twilio",
+        &params,
+        Some(&signature),
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert!(response.status().is_success());
+
+    let incoming_message: Message =
+        sqlx::query_as("SELECT * FROM messages WHERE message_sid = 'SM1849'")
+            .fetch_one(&db)
+            .await
+            .expect("Failed to fetch message");
+
+    assert_eq!(incoming_message.from_city, None);
+    assert_eq!(incoming_message.num_media, Some(1));
+    assert_eq!(incoming_message.sms_status, None);
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn twilio_repeats_the_reply_to_a_retried_message(db: PgPool) {
+    let mock_winnipeg_transit_api = MockServer::start().await;
+    let mock_stop_schedule_response = fs::read_to_string("tests/fixtures/times/stop_schedule.json")
+        .expect("Failed to read stop schedule fixture");
+
+    Mock::given(method("GET"))
+        .and(path_regex(r"^/v4/stops/.*/schedule.json$"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(mock_stop_schedule_response))
+        .expect(1)
+        .mount(&mock_winnipeg_transit_api)
+        .await;
+
+    let mut bodies = vec![];
+
+    for _ in 0..2 {
+        let response = get(
+            "/twilio?Body=10619&From=approved&To=textabus&MessageSid=SM1849",
+            InjectableServices {
+                db: db.clone(),
+                sms_sender: None,
+                twilio_address: None,
+                winnipeg_transit_api_address: Some(mock_winnipeg_transit_api.uri()),
+            },
+        )
+        .await
+        .expect("Failed to execute request");
+
+        assert!(response.status().is_success());
+        bodies.push(response.text().await.unwrap());
+    }
+
+    assert_that(&bodies[0]).contains("10619 WB Graham@Vaughan (The Bay)");
+    assert_eq!(bodies[0], bodies[1]);
+
+    let message_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM messages")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to fetch message count");
+
+    assert_eq!(message_count, 2);
+}
+
+async fn post_status(path: &str, params: &[(&str, &str)], db: &PgPool) -> reqwest::Response {
+    let signature = sign_form(path, params);
+
+    post_form(
+        path,
+        params,
+        Some(&signature),
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request")
+}
+
+#[sqlx::test(fixtures("numbers-approved"))]
+async fn twilio_reply_requests_a_status_callback_for_its_message(db: PgPool) {
+    let response = get(
+        "/twilio?Body=help&From=approved&To=textabus&MessageSid=SM1849",
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    let document = Document::from(response.text().await.unwrap().as_str());
+    let action = document
+        .find(Name("message"))
+        .next()
+        .unwrap()
+        .attr("action")
+        .unwrap()
+        .to_string();
+
+    let outgoing_message: Message =
+        sqlx::query_as("SELECT * FROM messages WHERE initial_message_id IS NOT NULL")
+            .fetch_one(&db)
+            .await
+            .expect("Failed to fetch message");
+
+    assert_eq!(
+        action,
+        format!(
+            "http://example.com/twilio/status?message_id={}",
+            outgoing_message.id
+        )
+    );
+
+    let status_path = format!("/twilio/status?message_id={}", outgoing_message.id);
+    let status_response = post_status(
+        &status_path,
+        &[("MessageSid", "SM1850"), ("MessageStatus", "sent")],
+        &db,
+    )
+    .await;
+
+    assert!(status_response.status().is_success());
+
+    let outgoing_message: Message = sqlx::query_as("SELECT * FROM messages WHERE id = $1")
+        .bind(outgoing_message.id)
+        .fetch_one(&db)
+        .await
+        .expect("Failed to fetch message");
+
+    assert_eq!(outgoing_message.message_sid, Some("SM1850".to_string()));
+    assert_eq!(outgoing_message.delivery_status, Some("sent".to_string()));
+}
+
+#[sqlx::test(fixtures("numbers-approved", "messages"))]
+async fn twilio_status_callback_records_delivery_by_message_sid(db: PgPool) {
+    sqlx::query("UPDATE messages SET message_sid = 'SM003' WHERE id = $1")
+        .bind(Uuid::parse_str("4addcf7f-dd8a-4cd8-94ca-e37c7d9f6519").unwrap())
+        .execute(&db)
+        .await
+        .expect("Failed to set message SID");
+
+    post_status(
+        "/twilio/status",
+        &[
+            ("MessageSid", "SM003"),
+            ("MessageStatus", "undelivered"),
+            ("ErrorCode", "30006"),
+        ],
+        &db,
+    )
+    .await;
+
+    let message: Message = sqlx::query_as("SELECT * FROM messages WHERE message_sid = 'SM003'")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to fetch message");
+
+    assert_eq!(message.delivery_status, Some("undelivered".to_string()));
+    assert_eq!(message.delivery_error_code, Some(30006));
+}
+
+#[sqlx::test(fixtures("numbers-approved", "messages"))]
+async fn twilio_status_callback_ignores_an_earlier_state_arriving_late(db: PgPool) {
+    sqlx::query(
+        "UPDATE messages SET message_sid = 'SM003', delivery_status = 'delivered' WHERE id = $1",
+    )
+    .bind(Uuid::parse_str("4addcf7f-dd8a-4cd8-94ca-e37c7d9f6519").unwrap())
+    .execute(&db)
+    .await
+    .expect("Failed to set message status");
+
+    post_status(
+        "/twilio/status",
+        &[
+            ("MessageSid", "SM003"),
+            ("MessageStatus", "sent"),
+            ("ErrorCode", ""),
+        ],
+        &db,
+    )
+    .await;
+
+    let message: Message = sqlx::query_as("SELECT * FROM messages WHERE message_sid = 'SM003'")
+        .fetch_one(&db)
+        .await
+        .expect("Failed to fetch message");
+
+    assert_eq!(message.delivery_status, Some("delivered".to_string()));
+}
+
+#[sqlx::test(fixtures("numbers-approved", "messages"))]
+async fn twilio_status_callback_rejects_an_invalid_signature(db: PgPool) {
+    let response = post_form(
+        "/twilio/status",
+        &[("MessageSid", "SM001"), ("MessageStatus", "failed")],
+        Some("invalid"),
+        InjectableServices {
+            db: db.clone(),
+            sms_sender: None,
+            twilio_address: None,
+            winnipeg_transit_api_address: None,
+        },
+    )
+    .await
+    .expect("Failed to execute request");
+
+    assert_eq!(response.status(), 403);
+
+    let status_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM messages WHERE delivery_status IS NOT NULL")
+            .fetch_one(&db)
+            .await
+            .expect("Failed to fetch status count");
+
+    assert_eq!(status_count, 0);
+}
@@ -3,46 +3,27 @@ mod helpers;
 use helpers::{get, get_config};
 
 use select::{document::Document, predicate::Name};
-use serde_json::json;
 use speculoos::prelude::*;
 use sqlx::postgres::PgPool;
+use std::sync::Arc;
 use textabus::{
     models::{Message, Number},
-    routes::HELP_MESSAGE,
+    sms::{RecordedSms, RecordingSmsSender},
     InjectableServices,
 };
-use wiremock::{
-    matchers::{body_string, method, path_regex},
-    Mock, MockServer, ResponseTemplate,
-};
 
 #[sqlx::test]
 async fn twilio_serves_welcome_to_and_registers_unknown_number_and_notifies_admin(db: PgPool) {
     let config = get_config();
 
-    let mock_twilio: MockServer = MockServer::start().await;
-
-    let twilio_create_message_body = serde_urlencoded::to_string([
-        ("Body", &"New number: unknown".to_string()),
-        ("To", &config.admin_number),
-        ("From", &config.textabus_number),
-    ])
-    .expect("Could not encode message creation body");
-
-    Mock::given(method("POST"))
-        .and(path_regex(r"^/2010-04-01/Accounts/.*/Messages.json$"))
-        .and(body_string(twilio_create_message_body.to_string()))
-        .respond_with(ResponseTemplate::new(201).set_body_json(json!({})))
-        .expect(1)
-        .named("create message")
-        .mount(&mock_twilio)
-        .await;
+    let sms_sender = Arc::new(RecordingSmsSender::default());
 
     let response = get(
         "/twilio?Body=hey&From=unknown&To=textabus&MessageSid=SM1312",
         InjectableServices {
             db: db.clone(),
-            twilio_address: Some(mock_twilio.uri()),
+            sms_sender: Some(sms_sender.clone()),
+            twilio_address: None,
             winnipeg_transit_api_address: None,
         },
     )
@@ -54,7 +35,7 @@ async fn twilio_serves_welcome_to_and_registers_unknown_number_and_notifies_admi
 
     let document = Document::from(response.text().await.unwrap().as_str());
 
-    assert_that(&document.find(Name("body")).next().unwrap().text()).contains("welcome to textabus. we don’t recognise you, please contact a maintainer to join the alpha test.");
+    assert_that(&document.find(Name("body")).next().unwrap().text()).contains("welcome to textabus. we don't recognise you, please contact a maintainer to join the alpha test.");
 
     let [incoming_message, admin_message, outgoing_message]: [Message; 3] =
         sqlx::query_as("SELECT * FROM messages ORDER BY created_at")
@@ -68,6 +49,16 @@ async fn twilio_serves_welcome_to_and_registers_unknown_number_and_notifies_admi
     assert_eq!(admin_message.origin, config.textabus_number);
     assert_eq!(admin_message.destination, config.admin_number);
     assert_eq!(admin_message.initial_message_id, Some(incoming_message.id));
+    assert_eq!(admin_message.message_sid, Some("SMFAKE1".to_string()));
+
+    assert_eq!(
+        *sms_sender.sent.lock().unwrap(),
+        vec![RecordedSms {
+            from: config.textabus_number.clone(),
+            to: config.admin_number.clone(),
+            body: "New number: unknown".to_string(),
+        }]
+    );
 
     assert_eq!(incoming_message.body, "hey");
     assert_that(&outgoing_message.body).contains("maintainer");
@@ -90,6 +81,7 @@ async fn twilio_ignores_a_known_but_not_approved_number(db: PgPool) {
         "/twilio?Body=hey&From=unapproved&To=textabus&MessageSid=SM1312",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: None,
         },
@@ -114,12 +106,11 @@ async fn twilio_ignores_a_known_but_not_approved_number(db: PgPool) {
 async fn twilio_serves_placeholder_with_unknown_body_to_approved_number_and_stores_messages(
     db: PgPool,
 ) {
-    let config = get_config();
-
     let response = get(
         "/twilio?Body=wha&From=approved&To=textabus&MessageSid=SM1312",
         InjectableServices {
             db: db.clone(),
+            sms_sender: None,
             twilio_address: None,
             winnipeg_transit_api_address: None,
         },
@@ -148,8 +139,8 @@ async fn twilio_serves_placeholder_with_unknown_body_to_approved_number_and_stor
     assert_eq!(incoming_message.destination, "textabus");
     assert_eq!(incoming_message.initial_message_id, None);
 
-    assert_that(&outgoing_message.body).contains(HELP_MESSAGE);
-    assert_that(&outgoing_message.body).contains(&config.root_url);
+    assert_that(&outgoing_message.body).starts_with("textabus commands:\n\nbus times:\n");
+    assert_that(&outgoing_message.body).ends_with("(more)");
 
     assert_eq!(outgoing_message.origin, "textabus");
     assert_eq!(outgoing_message.destination, "approved");